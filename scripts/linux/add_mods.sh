@@ -49,7 +49,7 @@ if [ -z "$VINTAGE_STORY_MODS" ]; then
 fi
 
 MOD_LIST=""
-echo "Type the Mods IDs. Type 'end' to finish."
+echo "Type the Mods IDs, use '$EXECUTABLE mods search <name>' to find them. Type 'end' to finish."
 
 while true; do
     read -p "Mod ID: " mod_id
@@ -70,8 +70,8 @@ while true; do
     fi
 done
 
-"$EXECUTABLE" \
-    --working-path "$VINTAGE_STORY" \
-    --mods-path "$VINTAGE_STORY_MODS" \
-    --generate-modpack $MOD_LIST \
-    --ignore-game-update
\ No newline at end of file
+for mod_id in ${MOD_LIST//,/ }; do
+    "$EXECUTABLE" mods add "$mod_id" \
+        --working-path "$VINTAGE_STORY" \
+        --mods-path "$VINTAGE_STORY_MODS"
+done
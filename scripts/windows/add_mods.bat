@@ -47,11 +47,11 @@ goto mod_loop
 
 :done_mods
 
-"%EXECUTABLE%" ^
-    --working-path "%VINTAGE_STORY%" ^
-    --mods-path "%VINTAGE_STORY_MODS%" ^
-    --generate-modpack "%MOD_LIST%" ^
-    --ignore-game-update
+for %%m in (%MOD_LIST%) do (
+    "%EXECUTABLE%" mods add %%m ^
+        --working-path "%VINTAGE_STORY%" ^
+        --mods-path "%VINTAGE_STORY_MODS%"
+)
 
 echo.
 echo Finished.
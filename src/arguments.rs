@@ -1,21 +1,59 @@
-use clap::Parser;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::logger::{LogLevel, LogsInstance};
+use crate::version::{GameVersion, VersionConstraint};
 
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
-pub(crate) struct Items {
+#[command(
+    author,
+    version,
+    about,
+    disable_version_flag = true,
+    after_help = "Breaking change: --version selects the game version to install, the version of the tool is printed with --tool-version or -V"
+)]
+pub struct Items {
+    /// Print the version of the tool, --version selects the game version
+    #[arg(short = 'V', long = "tool-version", action = ArgAction::Version)]
+    tool_version: Option<bool>,
+
+    /// More output, -v for debug and -vv for every command and request
+    #[arg(short = 'v', long = "verbose", action = ArgAction::Count, global = true, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Less output, -q for warnings and errors only, -qq for errors only
+    #[arg(short = 'q', long = "quiet", action = ArgAction::Count, global = true)]
+    pub quiet: u8,
+
+    /// Also write the log to .vs_updater_logs/vs_updater.log in the working path
+    #[arg(long, global = true)]
+    pub log_file: bool,
+
+    /// Size in MB after which the log file is rotated
+    #[arg(long, default_value_t = 5, global = true)]
+    pub log_max_size: u64,
+
+    /// Number of rotated log files kept
+    #[arg(long, default_value_t = 5, global = true)]
+    pub log_keep: usize,
+
     #[arg(long, value_delimiter = ',')]
     pub ignore_folders: Option<Vec<String>>,
 
     #[arg(long, value_delimiter = ',')]
     pub ignore_files: Option<Vec<String>>,
 
-    #[arg(long, value_delimiter = ',')]
+    /// Without install manifest, delete everything except the ignored items before the update
+    #[arg(long)]
+    pub clean_unmanaged: bool,
+
+    #[arg(long, value_delimiter = ',', global = true)]
     pub generate_modpack: Option<Vec<String>>,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub working_path: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub game_type: Option<String>,
 
     #[arg(long)]
@@ -24,18 +62,337 @@ pub(crate) struct Items {
     #[arg(long)]
     pub ignore_mod_update: bool,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub mods_path: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
+    pub mods_backup_path: Option<String>,
+
+    #[arg(long, conflicts_with = "game_version")]
     pub force_url: Option<String>,
 
+    /// Exact game version to install, also to downgrade. Not the tool version, see -V
+    #[arg(long = "version", value_name = "VERSION", global = true)]
+    pub game_version: Option<GameVersion>,
+
     #[arg(long)]
     pub no_pre_mods: bool,
 
     #[arg(long)]
     pub no_pre: bool,
 
-    #[arg(long)]
+    #[arg(long, value_enum, global = true)]
+    pub channel: Option<Channel>,
+
+    #[arg(long, value_enum, global = true)]
+    pub mods_channel: Option<Channel>,
+
+    #[arg(long, value_delimiter = ',', global = true)]
+    pub mod_channel: Option<Vec<String>>,
+
+    #[arg(long, global = true)]
+    pub max_version: Option<VersionConstraint>,
+
+    #[arg(long, global = true)]
+    pub target_version: Option<VersionConstraint>,
+
+    #[arg(long, global = true)]
     pub arch: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = SideFilter::Off, global = true)]
+    pub side_filter: SideFilter,
+
+    #[arg(long, visible_alias = "yes")]
+    pub non_interactive: bool,
+
+    #[arg(long, value_enum)]
+    pub leftover_temp: Option<LeftoverTemp>,
+
+    #[arg(long)]
+    pub countdown: Option<u64>,
+
+    #[arg(long)]
+    pub stop_server: bool,
+
+    #[arg(long)]
+    pub stop_command: Option<String>,
+
+    #[arg(long)]
+    pub start_command: Option<String>,
+
+    #[arg(long)]
+    pub server_console: Option<String>,
+
+    #[arg(long, default_value_t = 120)]
+    pub stop_timeout: u64,
+
+    #[arg(long, value_delimiter = ',')]
+    pub restart_warnings: Option<Vec<String>>,
+
+    #[arg(long)]
+    pub announce_command: Option<String>,
+
+    #[arg(long, default_value = "Server restarting for an update in {time}")]
+    pub restart_message: String,
+
+    #[arg(long)]
+    pub backup_worlds: bool,
+
+    #[arg(long, global = true)]
+    pub data_path: Option<String>,
+
+    #[arg(long, global = true)]
+    pub backup_path: Option<String>,
+
+    #[arg(long, default_value_t = 5)]
+    pub backup_keep: usize,
+
+    #[arg(long)]
+    pub hook_pre_discovery: Option<String>,
+
+    #[arg(long)]
+    pub hook_pre_clean: Option<String>,
+
+    #[arg(long)]
+    pub hook_post_extract: Option<String>,
+
+    #[arg(long)]
+    pub hook_post_mods: Option<String>,
+
+    #[arg(long)]
+    pub hook_on_failure: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SideFilter {
+    /// Update every mod
+    Off,
+    /// Skip mods that only run on the other side
+    Skip,
+    /// Update but warn about mods that only run on the other side
+    Flag,
+}
+
+/// Minimum stability accepted for the game or a mod
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Releases only
+    Stable,
+    /// Releases and release candidates
+    Rc,
+    /// Releases, release candidates and pre-releases
+    Pre,
+}
+
+impl Channel {
+    pub fn accepts(&self, version: &GameVersion) -> bool {
+        match self {
+            Channel::Stable => !version.is_pre() && !version.is_rc(),
+            Channel::Rc => !version.is_pre(),
+            Channel::Pre => true,
+        }
+    }
+
+    /// Checks a mod file name or version, unstable releases contain -pre or -rc
+    pub fn accepts_label(&self, label: &str) -> bool {
+        let label: String = label.to_lowercase();
+        match self {
+            Channel::Stable => !label.contains("-pre") && !label.contains("-rc"),
+            Channel::Rc => !label.contains("-pre"),
+            Channel::Pre => true,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeftoverTemp {
+    /// Stop without touching the folder
+    Abort,
+    /// Move the contents back to the working path
+    Restore,
+    /// Delete the folder
+    Discard,
+}
+
+impl Items {
+    /// Policy for a .temp folder left without journal, None asks the user
+    pub fn leftover_temp_policy(&self) -> Option<LeftoverTemp> {
+        match self.leftover_temp {
+            Some(policy) => Some(policy),
+            None if self.non_interactive => Some(LeftoverTemp::Abort),
+            None => None,
+        }
+    }
+
+    /// Level of the printed messages, from -v and -q
+    pub fn log_level(&self) -> LogLevel {
+        match (self.verbose, self.quiet) {
+            (0, 0) => LogLevel::Info,
+            (0, 1) => LogLevel::Warn,
+            (0, _) => LogLevel::Error,
+            (1, _) => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
+
+    /// Channel of the game, --no-pre is the same as --channel stable
+    pub fn game_channel(&self) -> Channel {
+        match self.channel {
+            Some(channel) => channel,
+            None if self.no_pre => Channel::Stable,
+            None => Channel::Pre,
+        }
+    }
+
+    /// Channel of a mod, from --mod-channel modid=channel, then --mods-channel and
+    /// --no-pre-mods
+    pub fn mod_channel(&self, modid: &str) -> Channel {
+        for entry in self.mod_channel.iter().flatten() {
+            let (id, channel): (&str, &str) = match entry.split_once('=') {
+                Some(pair) => pair,
+                None => {
+                    LogsInstance::warn(
+                        format!("Invalid --mod-channel entry ignored: {}", entry).as_str(),
+                        colored::Color::Yellow,
+                    );
+                    continue;
+                }
+            };
+
+            if !id.trim().eq_ignore_ascii_case(modid) {
+                continue;
+            }
+
+            match Channel::from_str(channel.trim(), true) {
+                Ok(channel) => return channel,
+                Err(_) => LogsInstance::warn(
+                    format!("Invalid channel for {} ignored: {}", id, channel).as_str(),
+                    colored::Color::Yellow,
+                ),
+            }
+        }
+
+        match self.mods_channel {
+            Some(channel) => channel,
+            None if self.no_pre_mods => Channel::Stable,
+            None => Channel::Pre,
+        }
+    }
+
+    /// True when the game version respects --channel, --target-version and --max-version
+    pub fn accepts_game_version(&self, version: &GameVersion) -> bool {
+        self.game_channel().accepts(version)
+            && self
+                .target_version
+                .as_ref()
+                .is_none_or(|target| target.matches(version))
+            && self
+                .max_version
+                .as_ref()
+                .is_none_or(|max| max.allows(version))
+    }
+
+    /// Seconds waited before cleaning the working path
+    pub fn countdown_seconds(&self) -> u64 {
+        match self.countdown {
+            Some(seconds) => seconds,
+            None if self.non_interactive => 0,
+            None => 5,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Install the game in an empty --working-path, at the latest version or --version
+    Install,
+    /// Show the game and mod changes made in --working-path, oldest first
+    History {
+        /// First day to show, YYYY-MM-DD in UTC
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Last day to show, YYYY-MM-DD in UTC
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only the changes of a mod page id
+        #[arg(long = "mod", value_name = "MODID", conflicts_with = "game")]
+        mod_id: Option<String>,
+
+        /// Only the game updates
+        #[arg(long)]
+        game: bool,
+
+        /// Print the history as json
+        #[arg(long)]
+        json: bool,
+
+        /// Number of most recent entries shown
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// Manage the mods inside --mods-path
+    Mods {
+        #[command(subcommand)]
+        action: ModsCommands,
+    },
+    /// Manage the world backups
+    Backups {
+        #[command(subcommand)]
+        action: BackupsCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupsCommands {
+    /// List the world backups, newest first
+    List,
+    /// Replace the saves with a backup, the current saves are backed up first
+    Restore { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ModsCommands {
+    /// Restore the last backup of a mod, the current version becomes the new backup
+    Revert { modid: String },
+    /// Search the mod database by name
+    Search {
+        text: String,
+
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+
+        /// Also show the latest release and game versions, one more request for each result
+        #[arg(long)]
+        versions: bool,
+    },
+    /// Install the newest release of a mod compatible with the installed game version
+    Add { slug: String },
+    /// List the installed mods and if an update is available
+    List {
+        /// Print the list as json
+        #[arg(long)]
+        json: bool,
+
+        /// Do not check the mod database for updates
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Remove a mod by the modid from modinfo.json or the mod page id
+    Remove {
+        modid: String,
+
+        /// Remove even if other installed mods depend on it
+        #[arg(long)]
+        force: bool,
+
+        /// Also remove dependencies that no other installed mod needs
+        #[arg(long)]
+        remove_orphans: bool,
+    },
 }
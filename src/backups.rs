@@ -0,0 +1,288 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
+
+use crate::arguments;
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+
+/// Items of the data path saved in each backup
+const BACKUP_ITEMS: [&str; 2] = ["Saves", "serverconfig.json"];
+
+pub struct WorldBackup {
+    enabled: bool,
+    data_path: PathBuf,
+    backup_path: PathBuf,
+    keep: usize,
+    created: bool,
+}
+
+impl WorldBackup {
+    pub fn new(loaded_arguments: &arguments::Items, working_path: &Path) -> WorldBackup {
+        let data_path: PathBuf = match &loaded_arguments.data_path {
+            Some(path) => PathBuf::from(path),
+            None => working_path.join("ServerData"),
+        };
+
+        let backup_path: PathBuf = match &loaded_arguments.backup_path {
+            Some(path) => PathBuf::from(path),
+            None => WorldBackup::default_path(working_path),
+        };
+
+        WorldBackup {
+            enabled: loaded_arguments.backup_worlds,
+            data_path,
+            backup_path,
+            keep: loaded_arguments.backup_keep,
+            created: false,
+        }
+    }
+
+    /// Next to the working path, like /home/user/vintagestory-backups, so the clean of an
+    /// update can never remove the backup it just took
+    fn default_path(working_path: &Path) -> PathBuf {
+        let working_path: PathBuf = working_path
+            .canonicalize()
+            .unwrap_or_else(|_| working_path.to_path_buf());
+
+        match (working_path.parent(), working_path.file_name()) {
+            (Some(parent), Some(name)) => {
+                parent.join(format!("{}-backups", name.to_string_lossy()))
+            }
+            _ => working_path.join("UpdaterBackups"),
+        }
+    }
+
+    fn extension() -> &'static str {
+        if cfg!(target_os = "windows") {
+            ".zip"
+        } else {
+            ".tar.gz"
+        }
+    }
+
+    /// Archives the saves once per run, called right before the first change to the
+    /// game or mods so the server is already stopped
+    pub fn create_once(&mut self) -> Result<(), UpdaterError> {
+        if !self.enabled || self.created {
+            return Ok(());
+        }
+
+        self.create()?;
+        self.prune();
+
+        Ok(())
+    }
+
+    fn create(&mut self) -> Result<(), UpdaterError> {
+        let items: Vec<&str> = BACKUP_ITEMS
+            .iter()
+            .copied()
+            .filter(|item| self.data_path.join(item).exists())
+            .collect();
+
+        if items.is_empty() {
+            LogsInstance::warn(
+                format!(
+                    "No saves found in {}, world backup skipped",
+                    self.data_path.display()
+                )
+                .as_str(),
+                colored::Color::Yellow,
+            );
+            self.created = true;
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.backup_path)
+            .map_err(|e| UpdaterError::Backup(format!("Cannot create backup folder: {}", e)))?;
+
+        let archive: PathBuf = self.backup_path.join(format!(
+            "world-{}{}",
+            Utils::file_timestamp(SystemTime::now()),
+            WorldBackup::extension()
+        ));
+
+        LogsInstance::print(
+            format!("Backing up the world to {}", archive.display()).as_str(),
+            colored::Color::White,
+        );
+
+        let status = if cfg!(target_os = "windows") {
+            let paths: Vec<String> = items
+                .iter()
+                .map(|item| format!("'{}'", self.data_path.join(item).display()))
+                .collect();
+            Command::new("powershell")
+                .arg(format!(
+                    "Compress-Archive -Path {} -DestinationPath '{}'",
+                    paths.join(","),
+                    archive.display()
+                ))
+                .status()
+        } else {
+            Command::new("tar")
+                .arg("-czf")
+                .arg(&archive)
+                .arg("-C")
+                .arg(&self.data_path)
+                .args(&items)
+                .status()
+        }
+        .map_err(|e| UpdaterError::Backup(format!("Failed to execute the archiver: {}", e)))?;
+
+        if !status.success() {
+            let _ = fs::remove_file(&archive);
+            return Err(UpdaterError::Backup(format!(
+                "World backup failed with status: {}",
+                status
+            )));
+        }
+
+        self.created = true;
+        LogsInstance::print("World backup created", colored::Color::Green);
+
+        Ok(())
+    }
+
+    /// Backups sorted from the oldest to the newest
+    pub fn list(&self) -> Vec<PathBuf> {
+        let mut backups: Vec<PathBuf> = match fs::read_dir(&self.backup_path) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let name: String = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    name.starts_with("world-") && name.ends_with(WorldBackup::extension())
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        // The timestamp in the name keeps the order
+        backups.sort();
+        backups
+    }
+
+    fn prune(&self) {
+        let backups: Vec<PathBuf> = self.list();
+        if backups.len() <= self.keep {
+            return;
+        }
+
+        for old in &backups[..backups.len() - self.keep] {
+            match fs::remove_file(old) {
+                Ok(_) => LogsInstance::print(
+                    format!("Old world backup removed: {}", old.display()).as_str(),
+                    colored::Color::White,
+                ),
+                Err(e) => LogsInstance::warn(
+                    format!("Cannot remove old world backup {}: {}", old.display(), e).as_str(),
+                    colored::Color::Yellow,
+                ),
+            }
+        }
+    }
+
+    pub fn print_list(&self) {
+        let backups: Vec<PathBuf> = self.list();
+
+        if backups.is_empty() {
+            LogsInstance::warn(
+                format!("No world backups in {}", self.backup_path.display()).as_str(),
+                colored::Color::Yellow,
+            );
+            return;
+        }
+
+        for backup in backups.iter().rev() {
+            let size: u64 = fs::metadata(backup).map(|m| m.len()).unwrap_or(0);
+            LogsInstance::print(
+                format!(
+                    "{:<40} {:>10.1} MB",
+                    backup
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    size as f64 / 1024.0 / 1024.0
+                )
+                .as_str(),
+                colored::Color::White,
+            );
+        }
+    }
+
+    /// Replaces the saves with the backup, the current saves are archived first
+    pub fn restore(&mut self, name: &str) -> Result<(), UpdaterError> {
+        // Only the file names printed by backups list, never a path out of the backup folder
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            return Err(UpdaterError::InvalidArgument(format!(
+                "Invalid backup name {}, use a name from backups list",
+                name
+            )));
+        }
+
+        let archive: PathBuf = self.backup_path.join(name);
+        if !archive.is_file() {
+            return Err(UpdaterError::Backup(format!(
+                "Backup not found: {}",
+                archive.display()
+            )));
+        }
+
+        // Keeps the current world in case the wrong backup was restored, without pruning
+        // so the backup being restored is never removed
+        self.create()?;
+
+        for item in BACKUP_ITEMS {
+            let path: PathBuf = self.data_path.join(item);
+            let result: std::io::Result<()> = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else if path.is_file() {
+                fs::remove_file(&path)
+            } else {
+                Ok(())
+            };
+            result.map_err(|e| {
+                UpdaterError::Backup(format!("Cannot remove {}: {}", path.display(), e))
+            })?;
+        }
+
+        fs::create_dir_all(&self.data_path)
+            .map_err(|e| UpdaterError::Backup(format!("Cannot create data path: {}", e)))?;
+
+        let status = if cfg!(target_os = "windows") {
+            Command::new("powershell")
+                .arg(format!(
+                    "Expand-Archive -Path '{}' -DestinationPath '{}' -Force",
+                    archive.display(),
+                    self.data_path.display()
+                ))
+                .status()
+        } else {
+            Command::new("tar")
+                .arg("-xzf")
+                .arg(&archive)
+                .arg("-C")
+                .arg(&self.data_path)
+                .status()
+        }
+        .map_err(|e| UpdaterError::Backup(format!("Failed to execute the archiver: {}", e)))?;
+
+        if !status.success() {
+            return Err(UpdaterError::Backup(format!(
+                "Restore failed with status: {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+}
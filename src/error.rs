@@ -0,0 +1,64 @@
+use std::fmt;
+
+use crate::version::ParseVersionError;
+
+/// Error of an update or install, the message is the one printed by the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdaterError {
+    /// The working path or the mods path cannot be used
+    InvalidPath(String),
+    /// An argument has a value that cannot be used, like a date or a backup name
+    InvalidArgument(String),
+    /// No version matches or the installed version cannot be read
+    Version(String),
+    /// A download or a request to the game or mod servers failed
+    Network(String),
+    /// The system or the game type has no release
+    Unsupported(String),
+    /// The server could not be stopped or started again
+    Server(String),
+    /// A hook exited non-zero
+    Hook(String),
+    /// A pre hook vetoed the update, nothing was changed
+    Cancelled(String),
+    /// A world or mod backup could not be created or restored
+    Backup(String),
+    /// The files of the install could not be replaced, the journal resumes on the next run
+    Install(String),
+    /// A mod could not be found, installed, removed or reverted
+    Mod(String),
+    Other(String),
+}
+
+impl UpdaterError {
+    pub fn message(&self) -> &str {
+        match self {
+            UpdaterError::InvalidPath(message)
+            | UpdaterError::InvalidArgument(message)
+            | UpdaterError::Version(message)
+            | UpdaterError::Network(message)
+            | UpdaterError::Unsupported(message)
+            | UpdaterError::Server(message)
+            | UpdaterError::Hook(message)
+            | UpdaterError::Cancelled(message)
+            | UpdaterError::Backup(message)
+            | UpdaterError::Install(message)
+            | UpdaterError::Mod(message)
+            | UpdaterError::Other(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for UpdaterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for UpdaterError {}
+
+impl From<ParseVersionError> for UpdaterError {
+    fn from(error: ParseVersionError) -> UpdaterError {
+        UpdaterError::Version(error.to_string())
+    }
+}
@@ -0,0 +1,510 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::arguments;
+use crate::backups::WorldBackup;
+use crate::error::UpdaterError;
+use crate::history::{History, HistoryAction, HistoryEntry};
+use crate::hooks::{Hook, Hooks};
+use crate::journal::{JournalStep, UpdateJournal};
+use crate::logger::LogsInstance;
+use crate::patterns::IgnorePatterns;
+use crate::resolver::VersionResolver;
+use crate::server::ServerLifecycle;
+use crate::state::{GameState, InstallState};
+use crate::utils::Utils;
+use crate::version::GameVersion;
+
+/// Updates or installs the game files of the working path of the arguments
+pub struct GameUpdater<'a> {
+    arguments: &'a arguments::Items,
+}
+
+impl<'a> GameUpdater<'a> {
+    pub fn new(loaded_arguments: &'a arguments::Items) -> GameUpdater<'a> {
+        GameUpdater {
+            arguments: loaded_arguments,
+        }
+    }
+
+    /// Replaces an existing install with the latest version allowed by the arguments, an
+    /// update not needed is not an error, a veto of a pre hook returns Cancelled
+    pub fn update(
+        &self,
+        server: &mut ServerLifecycle,
+        hooks: &mut Hooks,
+        backup: &mut WorldBackup,
+    ) -> Result<(), UpdaterError> {
+        let loaded_arguments: &arguments::Items = self.arguments;
+
+        // Getting work path
+        let (working_path, log_color): (PathBuf, colored::Color) =
+            Utils::get_working_path(&loaded_arguments.working_path)
+                .map_err(UpdaterError::InvalidPath)?;
+
+        LogsInstance::print(
+            format!("Working Directory: {:?}", working_path).as_str(),
+            log_color,
+        );
+
+        Utils::check_protected_path(&working_path).map_err(UpdaterError::InvalidPath)?;
+
+        if let Some(journal) =
+            UpdateJournal::recover(&working_path, loaded_arguments.leftover_temp_policy())?
+        {
+            hooks.old_version = Some(journal.old_version.clone());
+            hooks.new_version = Some(journal.version.clone());
+
+            server
+                .stop()
+                .map_err(|e| UpdaterError::Server(format!("Cannot stop the server: {}", e)))?;
+
+            return GameUpdater::install_version(
+                server,
+                hooks,
+                journal,
+                loaded_arguments.clean_unmanaged,
+            );
+        }
+
+        // An interrupted update no longer looks like an install, only checked after recovery
+        Utils::check_install_path(&working_path).map_err(UpdaterError::InvalidPath)?;
+
+        // Checked before anything is stopped or downloaded
+        if !Utils::has_manifest(&working_path) && !loaded_arguments.clean_unmanaged {
+            return Err(UpdaterError::InvalidPath(format!(
+                "No install manifest in {}, the files of the game are unknown. Run with --clean-unmanaged to delete everything except the ignored items once, or reinstall with the install command",
+                working_path.display()
+            )));
+        }
+
+        let patterns: IgnorePatterns = IgnorePatterns::from_arguments(loaded_arguments)
+            .map_err(UpdaterError::InvalidArgument)?;
+
+        let actual_game_version: GameVersion =
+            Utils::get_game_version(&working_path).ok_or_else(|| {
+                UpdaterError::Version(String::from(
+                    "Unknown game version, use the install command for a new install",
+                ))
+            })?;
+
+        if let Some(game) = &InstallState::load(&working_path).game
+            && game.version != actual_game_version
+        {
+            LogsInstance::warn(
+                format!(
+                    "The last install by the tool was {} but the game files are {}, the game was changed outside the tool",
+                    game.version, actual_game_version
+                )
+                .as_str(),
+                colored::Color::Yellow,
+            );
+        }
+
+        let resolver: VersionResolver = VersionResolver::new(loaded_arguments)?;
+
+        LogsInstance::print(
+            format!(
+                "Actual Version: {}{}{}",
+                resolver.game_type(),
+                actual_game_version,
+                Utils::get_compress_type()
+            )
+            .as_str(),
+            colored::Color::White,
+        );
+
+        hooks.old_version = Some(actual_game_version.to_string());
+
+        hooks
+            .run(Hook::PreDiscovery)
+            .map_err(|e| UpdaterError::Cancelled(format!("Update cancelled by the hook: {}", e)))?;
+
+        let url_download: String;
+        let mut last_version: GameVersion = GameVersion::default();
+        // If url is manually provided, get it
+        if let Some(url) = &loaded_arguments.force_url {
+            url_download = url.to_string();
+        }
+        // Exact version asked by the user, the channel and version constraints do not apply
+        else if let Some(version) = &loaded_arguments.game_version {
+            if *version == actual_game_version {
+                LogsInstance::print(
+                    format!("Version {} is already installed", version).as_str(),
+                    colored::Color::BrightGreen,
+                );
+                return Ok(());
+            }
+
+            url_download = resolver.available_url(version)?;
+
+            if *version < actual_game_version {
+                LogsInstance::warn(
+                    format!(
+                        "Downgrading from {} to {}, worlds saved by the newer version may not load, back them up first",
+                        actual_game_version, version
+                    )
+                    .as_str(),
+                    colored::Color::BrightYellow,
+                );
+            }
+
+            last_version = version.clone();
+        }
+        // If not we try to get it
+        else {
+            last_version = resolver.find_latest(&actual_game_version)?;
+
+            if last_version == actual_game_version {
+                LogsInstance::print("No update needed! :D", colored::Color::BrightGreen);
+                return Ok(());
+            } else if last_version < actual_game_version {
+                LogsInstance::print(
+                    "No update needed! :D (Your version is bigger than available versions)",
+                    colored::Color::BrightGreen,
+                );
+                return Ok(());
+            }
+
+            url_download = resolver.version_url(&last_version);
+        }
+
+        if !last_version.empty() {
+            hooks.new_version = Some(last_version.to_string());
+        }
+
+        hooks
+            .run(Hook::PreClean)
+            .map_err(|e| UpdaterError::Cancelled(format!("Update cancelled by the hook: {}", e)))?;
+
+        LogsInstance::warn(
+            format!(
+                "The files of the old version will be deleted in: {}, except for ignored!!",
+                working_path.display()
+            )
+            .as_str(),
+            colored::Color::BrightYellow,
+        );
+
+        if !server.announces_restart() {
+            Utils::countdown(
+                loaded_arguments.countdown_seconds(),
+                colored::Color::BrightRed,
+            );
+        }
+
+        server
+            .stop()
+            .map_err(|e| UpdaterError::Server(format!("Cannot stop the server: {}", e)))?;
+
+        backup.create_once().map_err(|e| {
+            UpdaterError::Backup(format!("Cannot backup the world, update cancelled: {}", e))
+        })?;
+
+        let mut journal: UpdateJournal = UpdateJournal::start(
+            &working_path,
+            actual_game_version.to_string(),
+            last_version.to_string(),
+            url_download,
+            resolver.arch().to_string(),
+            loaded_arguments.game_channel(),
+            loaded_arguments.game_version.is_some(),
+        )?;
+
+        let exe_path: PathBuf = std::env::current_exe().unwrap_or_default();
+        let exe_canonical: PathBuf = exe_path.canonicalize().unwrap_or(exe_path);
+        let preserved: Vec<String> = patterns.collect(&working_path, &|path: &Path| {
+            (path.parent() == Some(working_path.as_path())
+                && path.file_name().is_some_and(Utils::is_tool_item))
+                || path.canonicalize().ok().as_ref() == Some(&exe_canonical)
+        });
+
+        for item in &preserved {
+            if let Err(e) = journal.move_to_temp(item) {
+                journal.restore_items()?;
+                return Err(UpdaterError::Install(format!(
+                    "Cannot move {} to temp: {}",
+                    item, e
+                )));
+            }
+        }
+
+        GameUpdater::install_version(server, hooks, journal, loaded_arguments.clean_unmanaged)
+    }
+
+    /// Installs the version of the arguments, or the latest published one, in an empty
+    /// working path, an interrupted install is resumed
+    pub fn install(&self) -> Result<(), UpdaterError> {
+        let loaded_arguments: &arguments::Items = self.arguments;
+
+        let working_path: PathBuf = match &loaded_arguments.working_path {
+            Some(path) => {
+                fs::create_dir_all(path).map_err(|e| {
+                    UpdaterError::InvalidPath(format!(
+                        "Cannot create the working path {}: {}",
+                        path, e
+                    ))
+                })?;
+                PathBuf::from(path)
+            }
+            None => Utils::working_path(&None)?,
+        };
+
+        LogsInstance::print(
+            format!("Installing in: {}", working_path.display()).as_str(),
+            colored::Color::White,
+        );
+
+        Utils::check_protected_path(&working_path).map_err(UpdaterError::InvalidPath)?;
+
+        let mut server: ServerLifecycle = ServerLifecycle::new(loaded_arguments, &working_path);
+        let mut hooks: Hooks = Hooks::new(loaded_arguments, &working_path);
+
+        // An install interrupted after the clean is resumed like an update
+        if let Some(journal) =
+            UpdateJournal::recover(&working_path, loaded_arguments.leftover_temp_policy())?
+        {
+            hooks.new_version = Some(journal.version.clone());
+            return GameUpdater::install_version(&mut server, &mut hooks, journal, true);
+        }
+
+        // Only the tool files and the executable can be there
+        if !Utils::list_install_files(&working_path).is_empty() {
+            return Err(UpdaterError::InvalidPath(format!(
+                "{} is not empty, use the update mode for an existing install",
+                working_path.display()
+            )));
+        }
+
+        let resolver: VersionResolver = VersionResolver::new(loaded_arguments)?;
+
+        let version: GameVersion = match &loaded_arguments.game_version {
+            Some(version) => version.clone(),
+            None => resolver.latest_published()?,
+        };
+
+        let url_download: String = resolver.available_url(&version)?;
+
+        LogsInstance::print(
+            format!("Installing version {}", version).as_str(),
+            colored::Color::BrightGreen,
+        );
+
+        hooks.new_version = Some(version.to_string());
+
+        let journal: UpdateJournal = UpdateJournal::start(
+            &working_path,
+            String::new(),
+            version.to_string(),
+            url_download,
+            resolver.arch().to_string(),
+            loaded_arguments.game_channel(),
+            true,
+        )?;
+
+        GameUpdater::install_version(&mut server, &mut hooks, journal, true)
+    }
+
+    /// Installs the version of the journal and records the result in the history, unmanaged
+    /// allows the clean of an install without manifest, a new install is always empty
+    fn install_version(
+        server: &mut ServerLifecycle,
+        hooks: &mut Hooks,
+        journal: UpdateJournal,
+        unmanaged: bool,
+    ) -> Result<(), UpdaterError> {
+        let started: Instant = Instant::now();
+        let working_path: PathBuf = journal.working_path().to_path_buf();
+
+        let mut entry: HistoryEntry = HistoryEntry::new(HistoryAction::Game, "game");
+        entry.from = Some(journal.old_version.clone()).filter(|version| !version.is_empty());
+        entry.to = journal
+            .version
+            .parse::<GameVersion>()
+            .ok()
+            .filter(|version| *version != GameVersion::default())
+            .map(|version| version.to_string());
+        entry.url = Some(journal.url.clone());
+
+        let result: Result<(), UpdaterError> =
+            GameUpdater::apply_version(server, journal, unmanaged);
+
+        // A forced url is only known after the extraction
+        if result.is_ok()
+            && let Some(game) = InstallState::load(&working_path).game
+        {
+            entry.to = Some(game.version.to_string());
+        }
+
+        History::new(&working_path).record(entry, &result, started);
+        result?;
+
+        // After the history, a failing hook does not make the complete install a failure
+        hooks.run(Hook::PostExtract)
+    }
+
+    /// Replaces the game files following the journal, the steps already done by an
+    /// interrupted run are skipped
+    fn apply_version(
+        server: &mut ServerLifecycle,
+        mut journal: UpdateJournal,
+        unmanaged: bool,
+    ) -> Result<(), UpdaterError> {
+        let working_path: PathBuf = journal.working_path().to_path_buf();
+
+        let compressed_version: PathBuf = match &journal.archive {
+            Some(archive) if archive.is_file() => archive.clone(),
+            _ => match Utils::download_file(&journal.url, &working_path) {
+                Ok(archive) => {
+                    journal.archive = Some(archive.clone());
+                    archive
+                }
+                Err(e) => {
+                    if journal.step < JournalStep::Cleaned {
+                        journal.rollback()?;
+                        return Err(UpdaterError::Network(format!(
+                            "Failed to download the version: {}",
+                            e
+                        )));
+                    }
+                    return Err(UpdaterError::Network(format!(
+                        "Failed to download the version, the update will resume on the next run: {}",
+                        e
+                    )));
+                }
+            },
+        };
+
+        if journal.step < JournalStep::Downloaded {
+            match Utils::list_archive(&compressed_version) {
+                Ok(files) => journal.set_new_files(files)?,
+                Err(e) => {
+                    journal.rollback()?;
+                    return Err(UpdaterError::Install(format!(
+                        "Cannot list the files of the archive: {}",
+                        e
+                    )));
+                }
+            }
+            journal.set_step(JournalStep::Downloaded)?;
+        }
+
+        // Checked before the step is saved, so the next run rolls back instead of resuming
+        if journal.step < JournalStep::Cleaned && !unmanaged && !Utils::has_manifest(&working_path)
+        {
+            journal.rollback()?;
+            return Err(UpdaterError::InvalidPath(format!(
+                "No install manifest in {}, use --clean-unmanaged to delete everything except the ignored items",
+                working_path.display()
+            )));
+        }
+
+        server.mark_install_modified();
+
+        if journal.step < JournalStep::Extracted {
+            // Also cleans a partial extraction left by an interrupted run
+            journal.set_step(JournalStep::Cleaned)?;
+            match Utils::clean_working_path(
+                &working_path,
+                &journal.new_files,
+                &compressed_version,
+                unmanaged,
+            ) {
+                Ok(_) => LogsInstance::print("Working path cleared!", colored::Color::Green),
+                Err(e) => {
+                    return Err(UpdaterError::Install(format!(
+                        "Failed to clean working path: {}",
+                        e
+                    )));
+                }
+            }
+            let new_files: HashSet<&String> = journal.new_files.iter().collect();
+            let kept: Vec<String> = Utils::list_install_files(&working_path)
+                .into_iter()
+                .filter(|file| !new_files.contains(file))
+                .collect();
+            journal.set_kept(kept)?;
+
+            if let Err(e) = Utils::uncompress(&compressed_version, Some(journal.new_files.len())) {
+                return Err(UpdaterError::Install(format!(
+                    "Failed to uncompress, the update will resume on the next run: {}",
+                    e
+                )));
+            }
+
+            if journal.arch == "arm64" {
+                match Utils::patch_arm64(&working_path, &journal.version, journal.pinned) {
+                    Ok(_) => {
+                        LogsInstance::print("ARM64 binaries applied!", colored::Color::BrightGreen)
+                    }
+                    Err(e) => {
+                        return Err(UpdaterError::Install(format!(
+                            "Failed to apply ARM64 patch, the update will resume on the next run: {}",
+                            e
+                        )));
+                    }
+                }
+            }
+
+            let mut exclude: Vec<String> = journal.kept.clone();
+            if let Some(name) = compressed_version.file_name() {
+                exclude.push(name.to_string_lossy().to_string());
+            }
+            Utils::write_manifest(&working_path, &journal.new_files, &exclude)
+                .map_err(UpdaterError::Install)?;
+
+            journal.set_step(JournalStep::Extracted)?;
+        }
+
+        LogsInstance::print(
+            "Moving temp files to working path...",
+            colored::Color::White,
+        );
+
+        let hash: Option<String> = Utils::file_sha256(&compressed_version);
+
+        journal.finish()?;
+        server.mark_install_complete();
+
+        // A forced url has no known version, it is read from the extracted files
+        let version: Option<GameVersion> = journal
+            .version
+            .parse()
+            .ok()
+            .filter(|version| *version != GameVersion::default())
+            .or_else(|| Utils::get_game_version(&working_path));
+
+        let mut state: InstallState = InstallState::load(&working_path);
+        match version {
+            Some(version) => state.set_game(GameState {
+                version,
+                channel: journal.channel,
+                arch: journal.arch.clone(),
+                url: journal.url.clone(),
+                hash,
+                arm64_patched: journal.arch == "arm64",
+                installed_at: InstallState::now(),
+            }),
+            None => LogsInstance::warn(
+                "Unknown installed version, the game is not saved in the state",
+                colored::Color::Yellow,
+            ),
+        }
+        state.save_or_warn();
+
+        LogsInstance::print(
+            format!(
+                "Success!!!, your vintage story has been updated to {}",
+                journal.version
+            )
+            .as_str(),
+            colored::Color::BrightGreen,
+        );
+
+        Ok(())
+    }
+}
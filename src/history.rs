@@ -0,0 +1,201 @@
+use std::{
+    fmt, fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arguments;
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::state::InstallState;
+use crate::utils::Utils;
+
+/// Append-only log of the game and mod changes, one json entry per line, next to the install
+pub const HISTORY_FILE: &str = ".vs_updater_history.jsonl";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryAction {
+    Game,
+    ModUpdate,
+    ModAdd,
+    ModRemove,
+    ModRevert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix seconds of the end of the change
+    pub timestamp: u64,
+    /// Working path of the install that was changed
+    pub instance: String,
+    pub action: HistoryAction,
+    /// "game" or the mod page id
+    pub target: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub url: Option<String>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_secs: u64,
+}
+
+impl HistoryAction {
+    /// Name shown in the history table
+    pub fn label(self) -> &'static str {
+        match self {
+            HistoryAction::Game => "game",
+            HistoryAction::ModUpdate => "mod update",
+            HistoryAction::ModAdd => "mod add",
+            HistoryAction::ModRemove => "mod remove",
+            HistoryAction::ModRevert => "mod revert",
+        }
+    }
+}
+
+impl HistoryEntry {
+    pub fn new(action: HistoryAction, target: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: 0,
+            instance: String::new(),
+            action,
+            target: target.to_string(),
+            from: None,
+            to: None,
+            url: None,
+            success: false,
+            error: None,
+            duration_secs: 0,
+        }
+    }
+}
+
+pub struct History {
+    working_path: PathBuf,
+}
+
+impl History {
+    pub fn new(working_path: &Path) -> History {
+        History {
+            working_path: working_path.to_path_buf(),
+        }
+    }
+
+    pub fn from_arguments(loaded_arguments: &arguments::Items) -> Result<History, UpdaterError> {
+        Ok(History::new(&Utils::working_path(
+            &loaded_arguments.working_path,
+        )?))
+    }
+
+    /// Completes the entry with the result and the time since the change started, a history
+    /// that cannot be written only warns, the change itself is already done
+    pub fn record<T, E: fmt::Display>(
+        &self,
+        mut entry: HistoryEntry,
+        result: &Result<T, E>,
+        started: Instant,
+    ) {
+        entry.timestamp = InstallState::now();
+        entry.instance = self
+            .working_path
+            .canonicalize()
+            .unwrap_or_else(|_| self.working_path.clone())
+            .display()
+            .to_string();
+        entry.success = result.is_ok();
+        entry.error = result.as_ref().err().map(|e| e.to_string());
+        entry.duration_secs = started.elapsed().as_secs();
+
+        let path: PathBuf = self.working_path.join(HISTORY_FILE);
+        let written = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|line| {
+                fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .and_then(|mut file| writeln!(file, "{}", line))
+                    .map_err(|e| e.to_string())
+            });
+
+        if let Err(e) = written {
+            LogsInstance::warn(
+                format!("Cannot write the history {}: {}", path.display(), e).as_str(),
+                colored::Color::Yellow,
+            );
+        }
+    }
+
+    /// Entries in the order they happened, lines that cannot be read are skipped
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        let file: fs::File = match fs::File::open(self.working_path.join(HISTORY_FILE)) {
+            Ok(file) => file,
+            Err(_) => return Vec::new(),
+        };
+
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect()
+    }
+
+    /// Entries between the dates, of a mod or of the game, the last limit ones in the order
+    /// they happened
+    pub fn filter(
+        &self,
+        filter: &HistoryFilter,
+        limit: usize,
+    ) -> Result<Vec<HistoryEntry>, UpdaterError> {
+        let since: Option<u64> = match filter.since.as_deref().map(Utils::parse_date) {
+            Some(None) => return Err(History::invalid_date(filter.since.as_deref())),
+            Some(since) => since,
+            None => None,
+        };
+        // The whole day of --until is included
+        let until: Option<u64> = match filter.until.as_deref().map(Utils::parse_date) {
+            Some(None) => return Err(History::invalid_date(filter.until.as_deref())),
+            Some(until) => until.map(|until| until + 86400),
+            None => None,
+        };
+
+        let mut entries: Vec<HistoryEntry> = self
+            .entries()
+            .into_iter()
+            .filter(|entry| since.is_none_or(|since| entry.timestamp >= since))
+            .filter(|entry| until.is_none_or(|until| entry.timestamp < until))
+            .filter(|entry| !filter.game || entry.action == HistoryAction::Game)
+            .filter(|entry| {
+                filter
+                    .mod_id
+                    .as_deref()
+                    .is_none_or(|id| entry.target.eq_ignore_ascii_case(id))
+            })
+            .collect();
+        entries.drain(..entries.len().saturating_sub(limit));
+
+        Ok(entries)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.working_path.join(HISTORY_FILE)
+    }
+
+    fn invalid_date(date: Option<&str>) -> UpdaterError {
+        UpdaterError::InvalidArgument(format!(
+            "Invalid date {}, expected YYYY-MM-DD",
+            date.unwrap_or_default()
+        ))
+    }
+}
+
+/// Filters of the history command
+pub struct HistoryFilter {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub mod_id: Option<String>,
+    pub game: bool,
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use crate::arguments;
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreDiscovery,
+    PreClean,
+    PostExtract,
+    PostMods,
+    OnFailure,
+}
+
+impl Hook {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Hook::PreDiscovery => "pre-discovery",
+            Hook::PreClean => "pre-clean",
+            Hook::PostExtract => "post-extract",
+            Hook::PostMods => "post-mods",
+            Hook::OnFailure => "on-failure",
+        }
+    }
+}
+
+/// User commands executed at each step of the update, the context is sent as
+/// environment variables
+pub struct Hooks {
+    pre_discovery: Option<String>,
+    pre_clean: Option<String>,
+    post_extract: Option<String>,
+    post_mods: Option<String>,
+    on_failure: Option<String>,
+    working_path: PathBuf,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    pub changed_mods: Vec<String>,
+    pub failed_mods: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl Hooks {
+    pub fn new(loaded_arguments: &arguments::Items, working_path: &Path) -> Hooks {
+        Hooks {
+            pre_discovery: loaded_arguments.hook_pre_discovery.clone(),
+            pre_clean: loaded_arguments.hook_pre_clean.clone(),
+            post_extract: loaded_arguments.hook_post_extract.clone(),
+            post_mods: loaded_arguments.hook_post_mods.clone(),
+            on_failure: loaded_arguments.hook_on_failure.clone(),
+            working_path: working_path.to_path_buf(),
+            old_version: None,
+            new_version: None,
+            changed_mods: Vec::new(),
+            failed_mods: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn command(&self, hook: Hook) -> Option<&String> {
+        match hook {
+            Hook::PreDiscovery => self.pre_discovery.as_ref(),
+            Hook::PreClean => self.pre_clean.as_ref(),
+            Hook::PostExtract => self.post_extract.as_ref(),
+            Hook::PostMods => self.post_mods.as_ref(),
+            Hook::OnFailure => self.on_failure.as_ref(),
+        }
+    }
+
+    /// Runs the hook, an error means the command exited non-zero and the step must not
+    /// continue
+    pub fn run(&self, hook: Hook) -> Result<(), UpdaterError> {
+        let command: &String = match self.command(hook) {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+
+        LogsInstance::print(
+            format!("Running {} hook: {}", hook.name(), command).as_str(),
+            colored::Color::White,
+        );
+
+        let exit_status: &str = if self.error.is_some() || !self.failed_mods.is_empty() {
+            "1"
+        } else {
+            "0"
+        };
+
+        let envs: [(&str, String); 8] = [
+            ("VS_HOOK", hook.name().to_string()),
+            ("VS_WORKING_PATH", self.working_path.display().to_string()),
+            (
+                "VS_OLD_VERSION",
+                self.old_version.clone().unwrap_or_default(),
+            ),
+            (
+                "VS_NEW_VERSION",
+                self.new_version.clone().unwrap_or_default(),
+            ),
+            ("VS_CHANGED_MODS", self.changed_mods.join(",")),
+            ("VS_FAILED_MODS", self.failed_mods.join(",")),
+            ("VS_EXIT_STATUS", exit_status.to_string()),
+            ("VS_ERROR", self.error.clone().unwrap_or_default()),
+        ];
+
+        for (name, value) in &envs {
+            LogsInstance::trace(format!("{}={}", name, value).as_str());
+        }
+
+        Utils::run_shell_command(command, &envs)
+            .map_err(|e| UpdaterError::Hook(format!("The {} hook failed: {}", hook.name(), e)))
+    }
+
+    /// Runs the on-failure hook, its result is only logged
+    pub fn run_failure(&mut self, error: &str) {
+        self.error = Some(error.to_string());
+
+        if let Err(e) = self.run(Hook::OnFailure) {
+            LogsInstance::error(e.message(), colored::Color::BrightRed);
+        }
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arguments::{Channel, LeftoverTemp};
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+
+/// Journal of the game update in progress, kept in the working path and skipped by the clean
+pub const JOURNAL_FILE: &str = ".vs_updater_journal.json";
+pub const TEMP_FOLDER: &str = ".temp";
+
+/// Steps of the game update, in the order they happen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalStep {
+    /// The ignored items are being moved to .temp, nothing was deleted yet
+    Started,
+    /// The archive is in the working path and its files are listed, nothing was deleted yet
+    Downloaded,
+    /// Recorded before the clean starts, from here the old install is gone
+    Cleaned,
+    Extracted,
+}
+
+/// Every step of the game update is written to disk before continuing, so a run
+/// interrupted at any point can be rolled back or resumed by the next one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateJournal {
+    #[serde(skip)]
+    working_path: PathBuf,
+    pub step: JournalStep,
+    pub old_version: String,
+    pub version: String,
+    pub url: String,
+    pub arch: String,
+    /// Recorded in the state once the install is done
+    #[serde(default = "UpdateJournal::default_channel")]
+    pub channel: Channel,
+    /// Version asked with --version, the ARM64 patch must be for that exact version
+    #[serde(default)]
+    pub pinned: bool,
+    /// Preserved items moved to .temp, relative to the working path
+    pub moved: Vec<String>,
+    pub archive: Option<PathBuf>,
+    /// Files of the new version listed from the archive, they are not deleted by the clean
+    #[serde(default)]
+    pub new_files: Vec<String>,
+    /// Files left by the clean that are not in the archive, they are not part of the new
+    /// install
+    #[serde(default)]
+    pub kept: Vec<String>,
+}
+
+impl UpdateJournal {
+    pub fn start(
+        working_path: &Path,
+        old_version: String,
+        version: String,
+        url: String,
+        arch: String,
+        channel: Channel,
+        pinned: bool,
+    ) -> Result<UpdateJournal, UpdaterError> {
+        let journal: UpdateJournal = UpdateJournal {
+            working_path: working_path.to_path_buf(),
+            step: JournalStep::Started,
+            old_version,
+            version,
+            url,
+            arch,
+            channel,
+            pinned,
+            moved: Vec::new(),
+            archive: None,
+            new_files: Vec::new(),
+            kept: Vec::new(),
+        };
+
+        fs::create_dir_all(journal.temp_dir()).map_err(|e| {
+            UpdaterError::Install(format!("Error creating temporary directory: {}", e))
+        })?;
+        journal.save()?;
+
+        Ok(journal)
+    }
+
+    fn default_channel() -> Channel {
+        Channel::Stable
+    }
+
+    fn load(working_path: &Path) -> Result<UpdateJournal, UpdaterError> {
+        let path: PathBuf = working_path.join(JOURNAL_FILE);
+        let contents: String = fs::read_to_string(&path)
+            .map_err(|e| UpdaterError::Install(format!("Cannot read {}: {}", path.display(), e)))?;
+
+        let mut journal: UpdateJournal = serde_json::from_str(&contents).map_err(|e| {
+            UpdaterError::Install(format!(
+                "Invalid update journal {}, check the {} folder manually: {}",
+                path.display(),
+                TEMP_FOLDER,
+                e
+            ))
+        })?;
+        journal.working_path = working_path.to_path_buf();
+
+        Ok(journal)
+    }
+
+    /// Written to a temporary file and renamed, so a crash never leaves half a journal
+    fn save(&self) -> Result<(), UpdaterError> {
+        let path: PathBuf = self.working_path.join(JOURNAL_FILE);
+        let partial: PathBuf = self.working_path.join(format!("{}.partial", JOURNAL_FILE));
+
+        let contents: String = serde_json::to_string_pretty(self).map_err(|e| {
+            UpdaterError::Install(format!("Cannot serialize the update journal: {}", e))
+        })?;
+
+        fs::write(&partial, contents)
+            .and_then(|_| fs::File::open(&partial)?.sync_all())
+            .and_then(|_| fs::rename(&partial, &path))
+            .map_err(|e| UpdaterError::Install(format!("Cannot write the update journal: {}", e)))
+    }
+
+    pub fn working_path(&self) -> &Path {
+        &self.working_path
+    }
+
+    pub fn temp_dir(&self) -> PathBuf {
+        self.working_path.join(TEMP_FOLDER)
+    }
+
+    pub fn set_step(&mut self, step: JournalStep) -> Result<(), UpdaterError> {
+        LogsInstance::debug(format!("Update step: {:?}", step).as_str());
+        self.step = step;
+        self.save()
+    }
+
+    pub fn set_new_files(&mut self, new_files: Vec<String>) -> Result<(), UpdaterError> {
+        self.new_files = new_files;
+        self.save()
+    }
+
+    pub fn set_kept(&mut self, kept: Vec<String>) -> Result<(), UpdaterError> {
+        self.kept = kept;
+        self.save()
+    }
+
+    /// Moves a preserved item to the same relative path inside .temp, the journal is written
+    /// first so the item is never in .temp without being recorded
+    pub fn move_to_temp(&mut self, relative: &str) -> Result<(), UpdaterError> {
+        let source: PathBuf = self.working_path.join(relative);
+        let target_dir: PathBuf = match Path::new(relative).parent() {
+            Some(parent) => self.temp_dir().join(parent),
+            None => self.temp_dir(),
+        };
+
+        self.moved.push(relative.to_string());
+        self.save()?;
+
+        fs::create_dir_all(&target_dir).map_err(|e| UpdaterError::Install(e.to_string()))?;
+        Utils::move_item(&source, &target_dir).map_err(|e| UpdaterError::Install(e.to_string()))
+    }
+
+    /// Moves everything in .temp back to the working path, folders are merged so nested
+    /// items go back inside the new files, .temp and the journal are only removed when
+    /// nothing is left behind
+    pub fn restore_items(&self) -> Result<(), UpdaterError> {
+        let temp_dir: PathBuf = self.temp_dir();
+        let mut errors: Vec<String> = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(&temp_dir) {
+            for entry in entries.flatten() {
+                if let Err(e) = Utils::merge_item(&entry.path(), &self.working_path) {
+                    errors.push(format!("{}: {}", entry.file_name().to_string_lossy(), e));
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(UpdaterError::Install(format!(
+                "Cannot move back from {}: {}",
+                temp_dir.display(),
+                errors.join(", ")
+            )));
+        }
+
+        let _ = fs::remove_dir(&temp_dir);
+        let _ = fs::remove_file(self.working_path.join(JOURNAL_FILE));
+
+        Ok(())
+    }
+
+    /// Restores the ignored items and removes the archive, ending the update
+    pub fn finish(&self) -> Result<(), UpdaterError> {
+        self.restore_items()?;
+
+        if let Some(archive) = &self.archive {
+            let _ = fs::remove_file(archive);
+        }
+
+        Ok(())
+    }
+
+    /// Undoes an update that did not reach the clean, the ignored items are moved back and
+    /// the downloaded archive, complete or not, is removed
+    pub fn rollback(&self) -> Result<(), UpdaterError> {
+        if self.step >= JournalStep::Cleaned {
+            return Err(UpdaterError::Install(String::from(
+                "The old install was already cleaned, the update can only be resumed",
+            )));
+        }
+
+        self.restore_items()?;
+
+        // download_file saves the archive with the last segment of the url
+        let archive: PathBuf = match &self.archive {
+            Some(archive) => archive.clone(),
+            None => self
+                .working_path
+                .join(self.url.rsplit('/').next().unwrap_or_default()),
+        };
+        if archive.is_file() {
+            let _ = fs::remove_file(archive);
+        }
+
+        Ok(())
+    }
+
+    /// Handles a previous run that did not finish, returns the journal when the update must
+    /// be resumed, an update stopped before the clean is rolled back instead
+    pub fn recover(
+        working_path: &Path,
+        leftover_temp: Option<LeftoverTemp>,
+    ) -> Result<Option<UpdateJournal>, UpdaterError> {
+        if !working_path.join(JOURNAL_FILE).exists() {
+            Utils::check_temp_folder(working_path, leftover_temp).map_err(UpdaterError::Install)?;
+            return Ok(None);
+        }
+
+        let journal: UpdateJournal = UpdateJournal::load(working_path)?;
+
+        if journal.step == JournalStep::Started {
+            LogsInstance::warn(
+                format!(
+                    "The update to {} was interrupted before cleaning, rolling back...",
+                    journal.version
+                )
+                .as_str(),
+                colored::Color::Yellow,
+            );
+            journal.rollback()?;
+            LogsInstance::print("Rollback completed", colored::Color::Green);
+            return Ok(None);
+        }
+
+        LogsInstance::warn(
+            format!(
+                "The update from {} to {} was interrupted after the step: {:?}, resuming...",
+                journal.old_version, journal.version, journal.step
+            )
+            .as_str(),
+            colored::Color::Yellow,
+        );
+
+        Ok(Some(journal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty folder in the temp folder, removed before the test uses it
+    fn test_dir(name: &str) -> PathBuf {
+        let dir: PathBuf = std::env::temp_dir().join(format!(
+            "vs_updater_journal_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn start(working_path: &Path) -> UpdateJournal {
+        UpdateJournal::start(
+            working_path,
+            String::from("1.0.1"),
+            String::from("1.0.2"),
+            String::from("https://example.com/vs_server_linux-x64_1.0.2.tar.gz"),
+            String::from("linux-x64"),
+            Channel::Stable,
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn restore_items_merges_folders() {
+        let dir: PathBuf = test_dir("restore");
+        fs::create_dir_all(dir.join("Mods")).unwrap();
+        fs::write(dir.join("Mods/a.zip"), "a").unwrap();
+        fs::write(dir.join("serverconfig.json"), "{}").unwrap();
+
+        let mut journal: UpdateJournal = start(&dir);
+        journal.move_to_temp("Mods/a.zip").unwrap();
+        journal.move_to_temp("serverconfig.json").unwrap();
+        assert!(!dir.join("Mods/a.zip").exists());
+
+        // The new version brings its own files in the same folder
+        fs::write(dir.join("Mods/b.zip"), "b").unwrap();
+
+        journal.restore_items().unwrap();
+        assert!(dir.join("Mods/a.zip").is_file());
+        assert!(dir.join("Mods/b.zip").is_file());
+        assert!(dir.join("serverconfig.json").is_file());
+        assert!(!dir.join(TEMP_FOLDER).exists());
+        assert!(!dir.join(JOURNAL_FILE).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_items_keeps_temp_on_failure() {
+        let dir: PathBuf = test_dir("restore_failure");
+        let linked: PathBuf = dir.join("linked");
+        fs::create_dir_all(&linked).unwrap();
+        fs::create_dir_all(dir.join("Mods")).unwrap();
+
+        let journal: UpdateJournal = start(&dir);
+        fs::write(journal.temp_dir().join("serverconfig.json"), "{}").unwrap();
+        // A link to a folder is merged like a folder but cannot be removed with remove_dir
+        std::os::unix::fs::symlink(&linked, journal.temp_dir().join("Mods")).unwrap();
+
+        assert!(journal.restore_items().is_err());
+        assert!(dir.join("serverconfig.json").is_file());
+        assert!(journal.temp_dir().join("Mods").exists());
+        assert!(dir.join(JOURNAL_FILE).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rollback_removes_archive() {
+        let dir: PathBuf = test_dir("rollback");
+        fs::write(dir.join("serverconfig.json"), "{}").unwrap();
+
+        let mut journal: UpdateJournal = start(&dir);
+        journal.move_to_temp("serverconfig.json").unwrap();
+        fs::write(dir.join("vs_server_linux-x64_1.0.2.tar.gz"), "partial").unwrap();
+
+        journal.rollback().unwrap();
+        assert!(dir.join("serverconfig.json").is_file());
+        assert!(!dir.join("vs_server_linux-x64_1.0.2.tar.gz").exists());
+        assert!(!dir.join(TEMP_FOLDER).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rollback_refused_after_clean() {
+        let dir: PathBuf = test_dir("rollback_cleaned");
+
+        let mut journal: UpdateJournal = start(&dir);
+        journal.set_step(JournalStep::Cleaned).unwrap();
+
+        assert!(journal.rollback().is_err());
+        assert!(dir.join(JOURNAL_FILE).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recover_rolls_back_before_clean() {
+        let dir: PathBuf = test_dir("recover_started");
+        fs::write(dir.join("serverconfig.json"), "{}").unwrap();
+
+        let mut journal: UpdateJournal = start(&dir);
+        journal.move_to_temp("serverconfig.json").unwrap();
+
+        assert!(UpdateJournal::recover(&dir, None).unwrap().is_none());
+        assert!(dir.join("serverconfig.json").is_file());
+        assert!(!dir.join(JOURNAL_FILE).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recover_resumes_after_clean() {
+        let dir: PathBuf = test_dir("recover_cleaned");
+
+        let mut journal: UpdateJournal = start(&dir);
+        journal
+            .set_new_files(vec![String::from("VintagestoryServer.dll")])
+            .unwrap();
+        journal.set_step(JournalStep::Cleaned).unwrap();
+
+        let resumed: UpdateJournal = UpdateJournal::recover(&dir, None).unwrap().unwrap();
+        assert_eq!(resumed.step, JournalStep::Cleaned);
+        assert_eq!(resumed.version, "1.0.2");
+        assert_eq!(
+            resumed.new_files,
+            vec![String::from("VintagestoryServer.dll")]
+        );
+        assert_eq!(resumed.working_path(), dir.as_path());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn recover_leftover_temp() {
+        let dir: PathBuf = test_dir("recover_temp");
+        fs::create_dir_all(dir.join(TEMP_FOLDER)).unwrap();
+        fs::write(dir.join(TEMP_FOLDER).join("serverconfig.json"), "{}").unwrap();
+
+        assert!(UpdateJournal::recover(&dir, Some(LeftoverTemp::Abort)).is_err());
+        assert!(dir.join(TEMP_FOLDER).join("serverconfig.json").is_file());
+
+        assert!(
+            UpdateJournal::recover(&dir, Some(LeftoverTemp::Restore))
+                .unwrap()
+                .is_none()
+        );
+        assert!(dir.join("serverconfig.json").is_file());
+        assert!(!dir.join(TEMP_FOLDER).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
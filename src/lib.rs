@@ -0,0 +1,39 @@
+//! Updates a Vintage Story server or client install and its mods from the official
+//! releases and the mod database.
+//!
+//! Everything is driven by the command line [`arguments::Items`], built with
+//! `Items::parse_from` when embedding:
+//!
+//! - [`GameUpdater`] updates an existing install or installs a new one
+//! - [`ModUpdater`] updates the mods of the mods path
+//! - [`VersionResolver`] finds the published versions and their download urls
+//!
+//! Failures are returned as an [`UpdaterError`], progress and warnings go to the logger.
+
+pub mod arguments;
+pub mod backups;
+pub mod error;
+pub mod game;
+pub mod history;
+pub mod hooks;
+pub mod journal;
+pub mod lock;
+pub mod logger;
+pub mod moddb;
+pub mod modinfo;
+pub mod mods;
+pub mod patterns;
+pub mod progress;
+pub mod resolver;
+pub mod server;
+pub mod state;
+pub mod utils;
+pub mod version;
+
+pub use error::UpdaterError;
+pub use game::GameUpdater;
+pub use mods::ModUpdater;
+pub use resolver::VersionResolver;
+
+pub const BASE_URL: &str = "https://cdn.vintagestory.at/gamefiles/";
+pub const MODS_URL: &str = "https://mods.vintagestory.at/";
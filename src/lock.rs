@@ -0,0 +1,218 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::arguments;
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+
+/// Hidden so the clean and the mods scan skip it
+pub const LOCK_FILE: &str = ".vs_updater.lock";
+/// Age after which a lock without pid is considered stale
+const EMPTY_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Exclusive lock on the working path and mods path, released when dropped, a lock left by
+/// a process that is no longer running is taken over
+pub struct RunLock {
+    files: Vec<PathBuf>,
+}
+
+impl RunLock {
+    pub fn acquire(loaded_arguments: &arguments::Items) -> Result<RunLock, UpdaterError> {
+        let working_path: PathBuf =
+            match (&loaded_arguments.command, &loaded_arguments.working_path) {
+                // A fresh install creates its folder, it is locked before anything is written
+                (Some(arguments::Commands::Install), Some(path)) => {
+                    fs::create_dir_all(path).map_err(|e| {
+                        UpdaterError::InvalidPath(format!(
+                            "Cannot create the working path {}: {}",
+                            path, e
+                        ))
+                    })?;
+                    PathBuf::from(path)
+                }
+                _ => Utils::working_path(&loaded_arguments.working_path)?,
+            };
+
+        let mut paths: Vec<PathBuf> = vec![working_path];
+        if let Some(mods_path) = &loaded_arguments.mods_path {
+            // Nothing to protect in a mods folder that does not exist yet, the install creates
+            // it after the game under the lock of the working path
+            if Path::new(mods_path).is_dir() {
+                paths.push(PathBuf::from(mods_path));
+            }
+        }
+
+        let mut lock: RunLock = RunLock { files: Vec::new() };
+
+        for path in paths {
+            // The mods path can be inside the working path through a link or be the same folder
+            let path: PathBuf = path.canonicalize().unwrap_or(path);
+            let file: PathBuf = path.join(LOCK_FILE);
+            if lock.files.contains(&file) {
+                continue;
+            }
+
+            // On error the locks already taken are released by drop
+            RunLock::lock_file(&file).map_err(UpdaterError::InvalidPath)?;
+            lock.files.push(file);
+        }
+
+        Ok(lock)
+    }
+
+    /// The pid and start time are written to a file of this process first and linked into
+    /// place, the link fails when the lock exists so no run ever sees a half written lock
+    fn lock_file(file: &Path) -> Result<(), String> {
+        let started: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let partial: PathBuf = file.with_extension(format!("lock.{}", process::id()));
+
+        fs::write(&partial, format!("{}\n{}\n", process::id(), started))
+            .map_err(|e| format!("Cannot write lock {}: {}", partial.display(), e))?;
+
+        let result: Result<(), String> = RunLock::link_lock(&partial, file);
+        let _ = fs::remove_file(&partial);
+        result
+    }
+
+    fn link_lock(partial: &Path, file: &Path) -> Result<(), String> {
+        for _ in 0..2 {
+            match fs::hard_link(partial, file) {
+                Ok(_) => {
+                    LogsInstance::debug(format!("Lock taken: {}", file.display()).as_str());
+                    return Ok(());
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(format!("Cannot create lock {}: {}", file.display(), e)),
+            }
+
+            let contents: String = fs::read_to_string(file).unwrap_or_default();
+            let mut lines = contents.lines().map(|line| line.trim());
+            let pid: Option<u32> = lines.next().and_then(|line| line.parse().ok());
+            let started: Option<u64> = lines.next().and_then(|line| line.parse().ok());
+
+            let held: bool = match pid {
+                Some(pid) => Utils::process_alive(pid),
+                // Older versions wrote the pid after creating the file, an empty lock is only
+                // stale once it is old enough
+                None => RunLock::recently_modified(file),
+            };
+
+            if held {
+                let owner: String = match pid {
+                    Some(pid) => format!("pid {}", pid),
+                    None => String::from("unknown pid"),
+                };
+                let since: String = match started {
+                    Some(secs) => Utils::format_datetime(UNIX_EPOCH + Duration::from_secs(secs)),
+                    None => String::from("an unknown time"),
+                };
+                return Err(format!(
+                    "Another run ({}, started {}) holds the lock {}, remove it if that process is not the updater",
+                    owner,
+                    since,
+                    file.display()
+                ));
+            }
+
+            // Another run may have replaced the stale lock since it was read
+            if fs::read_to_string(file).unwrap_or_default() != contents {
+                continue;
+            }
+
+            LogsInstance::warn(
+                format!("Removing stale lock {}", file.display()).as_str(),
+                colored::Color::Yellow,
+            );
+            fs::remove_file(file)
+                .map_err(|e| format!("Cannot remove stale lock {}: {}", file.display(), e))?;
+        }
+
+        Err(format!("Cannot take the lock {}", file.display()))
+    }
+
+    fn recently_modified(file: &Path) -> bool {
+        fs::metadata(file)
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| modified.elapsed().unwrap_or_default() < EMPTY_LOCK_TIMEOUT)
+            .unwrap_or(false)
+    }
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        for file in &self.files {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Empty folder in the temp folder, removed before the test uses it
+    fn test_dir(name: &str) -> PathBuf {
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("vs_updater_lock_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn lock_written_complete() {
+        let dir: PathBuf = test_dir("complete");
+        let file: PathBuf = dir.join(LOCK_FILE);
+
+        RunLock::lock_file(&file).unwrap();
+        let contents: String = fs::read_to_string(&file).unwrap();
+        assert_eq!(
+            contents.lines().next(),
+            Some(process::id().to_string().as_str())
+        );
+        // Held by this process
+        assert!(RunLock::lock_file(&file).is_err());
+        // Only the lock is left
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn empty_lock_held_while_recent() {
+        let dir: PathBuf = test_dir("empty");
+        let file: PathBuf = dir.join(LOCK_FILE);
+        fs::write(&file, "").unwrap();
+
+        assert!(RunLock::lock_file(&file).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stale_lock_taken_over() {
+        let dir: PathBuf = test_dir("stale");
+        let file: PathBuf = dir.join(LOCK_FILE);
+        // Above the pid_max of Linux, never a running process
+        fs::write(&file, "4294967295\n0\n").unwrap();
+
+        RunLock::lock_file(&file).unwrap();
+        let contents: String = fs::read_to_string(&file).unwrap();
+        assert_eq!(
+            contents.lines().next(),
+            Some(process::id().to_string().as_str())
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
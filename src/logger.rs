@@ -1,30 +1,205 @@
+use std::{
+    env, fs,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::SystemTime,
+};
+
 use colored::*;
 
+use crate::arguments;
+use crate::utils::Utils;
+
+/// Folder of the log files inside the working path, skipped by the clean
+pub const LOGS_FOLDER: &str = ".vs_updater_logs";
+const LOG_FILE: &str = "vs_updater.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn label(self) -> &'static str {
+        match self {
+            LogLevel::Error => "ERROR",
+            LogLevel::Warn => "WARN",
+            LogLevel::Info => "INFO",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Trace => "TRACE",
+        }
+    }
+}
+
+struct Logger {
+    level: LogLevel,
+    color_stdout: bool,
+    color_stderr: bool,
+    file: Mutex<Option<fs::File>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
 pub struct LogsInstance {}
 
 impl LogsInstance {
+    /// Sets the level from the arguments, messages printed before use the info level
+    pub fn init(loaded_arguments: &arguments::Items) {
+        let _ = LOGGER.set(Logger {
+            level: loaded_arguments.log_level(),
+            color_stdout: LogsInstance::use_color(std::io::stdout().is_terminal()),
+            color_stderr: LogsInstance::use_color(std::io::stderr().is_terminal()),
+            file: Mutex::new(None),
+        });
+    }
+
+    /// Opens the log file asked with --log-file, called once the working path exists since
+    /// the install command creates it when taking the lock
+    pub fn open_log_file(loaded_arguments: &arguments::Items) {
+        if !loaded_arguments.log_file {
+            return;
+        }
+
+        let file: fs::File = match Utils::working_path(&loaded_arguments.working_path)
+            .map_err(|e| e.to_string())
+            .and_then(|working_path| {
+                LogsInstance::open_file(
+                    &working_path.join(LOGS_FOLDER),
+                    loaded_arguments.log_max_size * 1024 * 1024,
+                    loaded_arguments.log_keep,
+                )
+            }) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Cannot open the log file: {}", e);
+                return;
+            }
+        };
+
+        if let Some(logger) = LOGGER.get()
+            && let Ok(mut current) = logger.file.lock()
+        {
+            *current = Some(file);
+        }
+    }
+
+    /// Colors only for a terminal, NO_COLOR disables them and CLICOLOR_FORCE forces them
+    fn use_color(is_terminal: bool) -> bool {
+        if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return false;
+        }
+        if env::var("CLICOLOR_FORCE").is_ok_and(|value| value != "0") {
+            return true;
+        }
+        is_terminal
+    }
+
+    /// Opens the log for appending, a log bigger than max_size is rotated first to
+    /// vs_updater.log.1, the oldest of the keep rotated logs is removed
+    fn open_file(logs_path: &Path, max_size: u64, keep: usize) -> Result<fs::File, String> {
+        fs::create_dir_all(logs_path)
+            .map_err(|e| format!("Cannot create {}: {}", logs_path.display(), e))?;
+
+        let log: PathBuf = logs_path.join(LOG_FILE);
+        let rotated = |index: usize| logs_path.join(format!("{}.{}", LOG_FILE, index));
+
+        if fs::metadata(&log).is_ok_and(|metadata| metadata.len() >= max_size) {
+            if keep == 0 {
+                let _ = fs::remove_file(&log);
+            } else {
+                let _ = fs::remove_file(rotated(keep));
+                for index in (1..keep).rev() {
+                    let _ = fs::rename(rotated(index), rotated(index + 1));
+                }
+                fs::rename(&log, rotated(1))
+                    .map_err(|e| format!("Cannot rotate {}: {}", log.display(), e))?;
+            }
+        }
+
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log)
+            .map_err(|e| format!("Cannot open {}: {}", log.display(), e))
+    }
+
+    /// Prints an information message, hidden with -q
     pub fn print(log: &str, color: Color) {
-        let formated_color: ColoredString;
-        match color {
-            Color::Black => formated_color = log.black(),
-            Color::Red => formated_color = log.red(),
-            Color::Green => formated_color = log.green(),
-            Color::Yellow => formated_color = log.yellow(),
-            Color::Blue => formated_color = log.blue(),
-            Color::Magenta => formated_color = log.magenta(),
-            Color::Cyan => formated_color = log.cyan(),
-            Color::White => formated_color = log.white(),
-            Color::BrightBlack => formated_color = log.bright_black(),
-            Color::BrightRed => formated_color = log.bright_red(),
-            Color::BrightGreen => formated_color = log.bright_green(),
-            Color::BrightYellow => formated_color = log.bright_yellow(),
-            Color::BrightBlue => formated_color = log.bright_blue(),
-            Color::BrightMagenta => formated_color = log.bright_magenta(),
-            Color::BrightCyan => formated_color = log.bright_cyan(),
-            Color::BrightWhite => formated_color = log.bright_white(),
-            // Default
-            _ => formated_color = log.white(),
-        }
-        println!("{}", formated_color);
-    }
-}
\ No newline at end of file
+        LogsInstance::log(LogLevel::Info, log, color);
+    }
+
+    /// Prints a warning, hidden with -qq
+    pub fn warn(log: &str, color: Color) {
+        LogsInstance::log(LogLevel::Warn, log, color);
+    }
+
+    /// Prints an error to stderr, always shown
+    pub fn error(log: &str, color: Color) {
+        LogsInstance::log(LogLevel::Error, log, color);
+    }
+
+    pub fn level() -> LogLevel {
+        LOGGER.get().map_or(LogLevel::Info, |logger| logger.level)
+    }
+
+    /// Details shown with -v
+    pub fn debug(log: &str) {
+        LogsInstance::log(LogLevel::Debug, log, Color::BrightBlack);
+    }
+
+    /// Every command and request, shown with -vv
+    pub fn trace(log: &str) {
+        LogsInstance::log(LogLevel::Trace, log, Color::BrightBlack);
+    }
+
+    fn log(level: LogLevel, log: &str, color: Color) {
+        let logger: Option<&Logger> = LOGGER.get();
+
+        // The file keeps at least the information messages, even for quiet runs
+        if let Some(logger) = logger
+            && level <= logger.level.max(LogLevel::Info)
+            && let Ok(mut file) = logger.file.lock()
+            && let Some(file) = file.as_mut()
+        {
+            let _ = writeln!(
+                file,
+                "{} [{}] {}",
+                Utils::format_datetime(SystemTime::now()),
+                level.label(),
+                log
+            );
+        }
+
+        if level > LogsInstance::level() {
+            return;
+        }
+
+        let is_error: bool = level == LogLevel::Error;
+        let colored: bool = match logger {
+            Some(logger) if is_error => logger.color_stderr,
+            Some(logger) => logger.color_stdout,
+            None => LogsInstance::use_color(if is_error {
+                std::io::stderr().is_terminal()
+            } else {
+                std::io::stdout().is_terminal()
+            }),
+        };
+
+        let formated: String = if colored {
+            log.color(color).to_string()
+        } else {
+            log.to_string()
+        };
+
+        if is_error {
+            eprintln!("{}", formated);
+        } else {
+            println!("{}", formated);
+        }
+    }
+}
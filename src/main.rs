@@ -1,757 +1,382 @@
 use std::{
-    env, fs,
-    io::Write,
-    path::{Path, PathBuf},
-    process,
+    fs,
+    path::PathBuf,
+    process::ExitCode,
+    time::{Duration, UNIX_EPOCH},
 };
 
 use clap::Parser;
-use logger::LogsInstance;
-use utils::GameVersion;
-use utils::Utils;
-
-mod arguments;
-mod logger;
-mod utils;
-
-const BASE_URL: &str = "https://cdn.vintagestory.at/gamefiles/";
-const MODS_URL: &str = "https://mods.vintagestory.at/";
-
-fn main() {
+use vintagestory_updater::arguments;
+use vintagestory_updater::backups::WorldBackup;
+use vintagestory_updater::history::{History, HistoryEntry, HistoryFilter};
+use vintagestory_updater::hooks::Hooks;
+use vintagestory_updater::lock::RunLock;
+use vintagestory_updater::logger::LogsInstance;
+use vintagestory_updater::mods::{self, ModListEntry};
+use vintagestory_updater::server::ServerLifecycle;
+use vintagestory_updater::utils::Utils;
+use vintagestory_updater::{GameUpdater, ModUpdater, UpdaterError};
+
+/// Exits with 1 when an error was reported, a run cancelled by a hook is not an error
+fn main() -> ExitCode {
     #[cfg(windows)]
     match colored::control::set_virtual_terminal(true) {
         Ok(_) => {}
         Err(_) => eprintln!("Cannot enable virtual terminal"),
     }
 
-    if cfg!(target_os = "linux") {
-        if !Utils::command_exists("wget") {
-            LogsInstance::print("Missing 'wget' dependency", colored::Color::BrightRed);
-            process::exit(1);
-        }
+    if cfg!(target_os = "linux") && !Utils::command_exists("wget") {
+        LogsInstance::error("Missing 'wget' dependency", colored::Color::BrightRed);
+        return ExitCode::FAILURE;
     }
 
     let loaded_arguments: arguments::Items = arguments::Items::parse();
+    LogsInstance::init(&loaded_arguments);
+
+    // Listing and searching do not change anything, they can run next to an update
+    let read_only: bool = matches!(
+        &loaded_arguments.command,
+        Some(arguments::Commands::Mods {
+            action: arguments::ModsCommands::List { .. } | arguments::ModsCommands::Search { .. }
+        }) | Some(arguments::Commands::Backups {
+            action: arguments::BackupsCommands::List
+        }) | Some(arguments::Commands::History { .. })
+    );
 
-    if loaded_arguments.generate_modpack.is_some() {
-        generate_mods(&loaded_arguments);
-    }
-
-    if !loaded_arguments.ignore_game_update {
-        update_game(&loaded_arguments);
-    }
-
-    if !loaded_arguments.ignore_mod_update && loaded_arguments.mods_path.is_some() {
-        update_mods(&loaded_arguments);
-    }
-}
-
-fn update_game(loaded_arguments: &arguments::Items) {
-    // Getting work path
-    let log_color: colored::Color;
-    let working_path: PathBuf = if let Some(path) = &loaded_arguments.working_path {
-        let path_buf: PathBuf = Path::new(path).to_path_buf();
-
-        if path_buf.exists() && path_buf.is_dir() {
-            log_color = colored::Color::Green;
-            path_buf
-        } else {
-            LogsInstance::print(
-                format!("The working-path: {}, is invalid", path).as_str(),
-                colored::Color::BrightRed,
-            );
-            return;
-        }
+    let _lock: Option<RunLock> = if read_only {
+        None
     } else {
-        match env::var("VINTAGE_STORY") {
-            Ok(value) => {
-                log_color = colored::Color::BrightGreen;
-                PathBuf::from(value)
-            }
-            Err(_) => {
-                log_color = colored::Color::Yellow;
-
-                env::current_exe()
-                    .ok()
-                    .and_then(|exe_path: PathBuf| exe_path.parent().map(|p: &Path| p.to_path_buf()))
-                    .unwrap_or_else(|| PathBuf::from("."))
+        match RunLock::acquire(&loaded_arguments) {
+            Ok(lock) => Some(lock),
+            Err(e) => {
+                LogsInstance::error(e.message(), colored::Color::BrightRed);
+                return ExitCode::FAILURE;
             }
         }
     };
 
-    LogsInstance::print(
-        format!("Working Directory: {:?}", working_path).as_str(),
-        log_color,
-    );
+    LogsInstance::open_log_file(&loaded_arguments);
 
-    Utils::check_temp_folder(&working_path);
+    if let Some(command) = &loaded_arguments.command {
+        let result: Result<(), UpdaterError> = match command {
+            arguments::Commands::Mods { action } => match action {
+                arguments::ModsCommands::Revert { modid } => {
+                    mods::revert_mod(&loaded_arguments, modid)
+                }
+                arguments::ModsCommands::Search {
+                    text,
+                    limit,
+                    versions,
+                } => mods::search_mods(text, *limit, *versions),
+                arguments::ModsCommands::Add { slug } => mods::add_mod(&loaded_arguments, slug),
+                arguments::ModsCommands::List { json, offline } => {
+                    mods::list_mods(&loaded_arguments, *offline)
+                        .and_then(|entries| print_mod_list(&entries, *json))
+                }
+                arguments::ModsCommands::Remove {
+                    modid,
+                    force,
+                    remove_orphans,
+                } => mods::remove_mod(&loaded_arguments, modid, *force, *remove_orphans),
+            },
+            arguments::Commands::Install => install_game(&loaded_arguments),
+            arguments::Commands::History {
+                since,
+                until,
+                mod_id,
+                game,
+                json,
+                limit,
+            } => History::from_arguments(&loaded_arguments).and_then(|history| {
+                let entries: Vec<HistoryEntry> = history.filter(
+                    &HistoryFilter {
+                        since: since.clone(),
+                        until: until.clone(),
+                        mod_id: mod_id.clone(),
+                        game: *game,
+                    },
+                    *limit,
+                )?;
+                print_history(&history, &entries, *json)
+            }),
+            arguments::Commands::Backups { action } => match action {
+                arguments::BackupsCommands::List => backups_list(&loaded_arguments),
+                arguments::BackupsCommands::Restore { name } => {
+                    backups_restore(&loaded_arguments, name)
+                }
+            },
+        };
 
-    let temp_dir: PathBuf = working_path.join(".temp");
+        return match result {
+            Ok(_) => ExitCode::SUCCESS,
+            Err(UpdaterError::Cancelled(message)) => {
+                LogsInstance::warn(message.as_str(), colored::Color::Yellow);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                LogsInstance::error(e.message(), colored::Color::BrightRed);
+                ExitCode::FAILURE
+            }
+        };
+    }
 
-    if let Err(e) = std::fs::create_dir_all(&temp_dir) {
-        LogsInstance::print(
-            format!("Error creating temporary directory: {}", e).as_str(),
-            colored::Color::BrightRed,
-        );
+    // Any error reported below makes the exit code 1, the run still goes on
+    let mut failed: bool = false;
+
+    let mod_updater: ModUpdater = ModUpdater::new(&loaded_arguments);
 
-        return;
+    if loaded_arguments.generate_modpack.is_some()
+        && let Err(e) = mod_updater.generate()
+    {
+        LogsInstance::error(e.message(), colored::Color::BrightRed);
+        failed = true;
     }
 
-    if let Some(folders) = &loaded_arguments.ignore_folders {
-        for folder in folders {
-            let full_folder_path: PathBuf = working_path.join(&folder);
-            if let Err(e) = Utils::move_item(&full_folder_path, &temp_dir) {
-                LogsInstance::print(
-                    format!("Cannot move folder to temp: {}", e).as_str(),
-                    colored::Color::BrightRed,
-                );
-                return;
-            }
+    let working_path: PathBuf = match Utils::working_path(&loaded_arguments.working_path) {
+        Ok(path) => path,
+        Err(e) => {
+            LogsInstance::error(e.message(), colored::Color::BrightRed);
+            return ExitCode::FAILURE;
         }
-    }
+    };
+    let mut server: ServerLifecycle = ServerLifecycle::new(&loaded_arguments, &working_path);
+    let mut hooks: Hooks = Hooks::new(&loaded_arguments, &working_path);
+    let mut backup: WorldBackup = WorldBackup::new(&loaded_arguments, &working_path);
 
-    if let Some(files) = &loaded_arguments.ignore_files {
-        for file in files {
-            let full_file_path: PathBuf = working_path.join(&file);
-            if let Err(e) = Utils::move_item(&full_file_path, &temp_dir) {
-                LogsInstance::print(
-                    format!("Cannot move file to temp: {}", e).as_str(),
-                    colored::Color::BrightRed,
-                );
-                return;
+    // A failed game update leaves the install incomplete, the server cannot run from it
+    let mut can_restart: bool = true;
+
+    // A veto of a pre hook stops the whole run, the mods are not updated either
+    let mut cancelled: bool = false;
+
+    if !loaded_arguments.ignore_game_update {
+        match GameUpdater::new(&loaded_arguments).update(&mut server, &mut hooks, &mut backup) {
+            Ok(_) => {}
+            Err(UpdaterError::Cancelled(message)) => {
+                LogsInstance::warn(message.as_str(), colored::Color::Yellow);
+                cancelled = true;
+            }
+            Err(e) => {
+                LogsInstance::error(e.message(), colored::Color::BrightRed);
+                can_restart = !server.install_modified();
+                failed = true;
+                hooks.run_failure(e.message());
             }
         }
     }
 
-    let mut game_version: GameVersion;
-    if let Some(version) = Utils::get_game_version(&working_path) {
-        game_version = match GameVersion::from_str(&version) {
-            Some(ver) => ver,
-            None => {
-                LogsInstance::print(
-                    format!("Invalid game version: {}", version).as_str(),
-                    colored::Color::BrightRed,
-                );
-                return;
+    if !cancelled && !loaded_arguments.ignore_mod_update && loaded_arguments.mods_path.is_some() {
+        let error: Option<String> = match mod_updater.update(&mut server, &mut hooks, &mut backup) {
+            Err(e) => {
+                LogsInstance::error(e.message(), colored::Color::BrightRed);
+                Some(e.to_string())
             }
+            Ok(_) if !hooks.failed_mods.is_empty() => Some(format!(
+                "Failed to update mods: {}",
+                hooks.failed_mods.join(",")
+            )),
+            Ok(_) => None,
         };
-    } else {
-        LogsInstance::print(
-            "Unknown game version, add a file in assets/version-1.0.0.txt",
-            colored::Color::BrightRed,
-        );
-        return;
-    }
-
-    let actual_game_version: GameVersion = game_version.clone();
 
-    let detected_arch = if std::env::consts::ARCH == "aarch64" { "arm64" } else { "x64" };
-    let arch: &str = loaded_arguments.arch.as_deref().unwrap_or(detected_arch);
-
-    let game_type: String;
-    if let Some(_type) = &loaded_arguments.game_type {
-        game_type = Utils::get_game_type(&_type);
-    } else {
-        game_type = Utils::get_game_type(&String::from("server"));
+        if let Some(error) = error {
+            failed = true;
+            hooks.run_failure(&error);
+        }
     }
 
-    LogsInstance::print(
-        format!(
-            "Actual Version: {}{}{}",
-            game_type,
-            game_version.to_string(),
-            Utils::get_compress_type()
-        )
-        .as_str(),
-        colored::Color::White,
-    );
-
-    game_version.remove_pre_and_rc();
-
-    let url_download: String;
-    let mut last_version: GameVersion = GameVersion::from_str("0.0.0").unwrap();
-    // If url is manually provided, get it
-    if let Some(url) = &loaded_arguments.force_url {
-        url_download = url.to_string();
-    }
-    // If not we try to get it
-    else {
-        loop {
-            let ping_url: String;
-            if game_version.is_pre() || game_version.is_rc() {
-                ping_url = format!(
-                    "{}unstable/{}{}{}",
-                    BASE_URL,
-                    game_type,
-                    game_version.to_string(),
-                    Utils::get_compress_type()
-                );
-            } else {
-                ping_url = format!(
-                    "{}stable/{}{}{}",
-                    BASE_URL,
-                    game_type,
-                    game_version.to_string(),
-                    Utils::get_compress_type()
+    if server.is_stopped() {
+        if can_restart {
+            if let Err(e) = server.restart() {
+                LogsInstance::error(
+                    format!("Failed to restart the server: {}", e).as_str(),
+                    colored::Color::BrightRed,
                 );
+                failed = true;
             }
-
-            LogsInstance::print(
-                format!("Pinging: {}", ping_url).as_str(),
-                colored::Color::White,
+        } else {
+            LogsInstance::error(
+                "The server was not restarted because the game update failed",
+                colored::Color::BrightRed,
             );
-
-            if Utils::url_exists(&ping_url) {
-                LogsInstance::print(
-                    format!("Version available: {}", game_version.to_string()).as_str(),
-                    colored::Color::Green,
-                );
-                last_version = game_version.clone();
-
-                if loaded_arguments.no_pre {
-                    if game_version.is_pre() {
-                        game_version.increment_pre();
-                    } else if game_version.is_rc() {
-                        game_version.increment_rc();
-                    } else {
-                        game_version.increment_patch();
-                    }
-                } else {
-                    game_version.increment_patch();
-                }
-            } else {
-                if loaded_arguments.no_pre {
-                    if game_version.minor != actual_game_version.minor {
-                        if game_version.major != actual_game_version.major {
-                            LogsInstance::print(
-                                format!(
-                                    "Latest version available: {}, installed version: {}",
-                                    last_version.to_string(),
-                                    actual_game_version.to_string()
-                                )
-                                .as_str(),
-                                colored::Color::BrightGreen,
-                            );
-                            break;
-                        } else {
-                            game_version.increment_major();
-                        }
-                    } else {
-                        game_version.increment_minor();
-                    }
-                } else {
-                    if game_version.minor != actual_game_version.minor {
-                        if game_version.major != actual_game_version.major {
-                            LogsInstance::print(
-                                format!(
-                                    "Latest version available: {}, installed version: {}",
-                                    last_version.to_string(),
-                                    actual_game_version.to_string()
-                                )
-                                .as_str(),
-                                colored::Color::BrightGreen,
-                            );
-                            break;
-                        } else {
-                            game_version.increment_major();
-                        }
-                    } else {
-                        game_version.increment_minor();
-                    }
-                }
-            }
         }
+    }
 
-        if !loaded_arguments.no_pre {
-            // No release available to update
-            if last_version.empty() {
-                // Checking for unstable on actual game version
-                if actual_game_version.is_pre() || actual_game_version.is_rc() {
-                    game_version = actual_game_version.clone();
-                }
-                // Actual version is not unstable
-                else {
-                    game_version = actual_game_version.clone();
-                    game_version.increment_minor();
-                    game_version.increment_pre();
-                }
-            }
-            // Final release, increase minor for checking unstables
-            else {
-                game_version = last_version.clone();
-                game_version.increment_minor();
-                game_version.increment_pre();
-            }
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
 
-            loop {
-                let ping_url: String = format!(
-                    "{}unstable/{}{}{}",
-                    BASE_URL,
-                    game_type,
-                    game_version.to_string(),
-                    Utils::get_compress_type()
-                );
+fn install_game(loaded_arguments: &arguments::Items) -> Result<(), UpdaterError> {
+    GameUpdater::new(loaded_arguments).install()?;
 
-                LogsInstance::print(
-                    format!("Pinging: {}", ping_url).as_str(),
-                    colored::Color::White,
-                );
+    let mods_path: &String = match &loaded_arguments.mods_path {
+        Some(path) => path,
+        None => return Ok(()),
+    };
 
-                if Utils::url_exists(&ping_url) {
-                    LogsInstance::print(
-                        format!("Unstable Version available: {}", game_version.to_string())
-                            .as_str(),
-                        colored::Color::BrightGreen,
-                    );
-                    last_version = game_version.clone();
-
-                    if game_version.is_rc() {
-                        game_version.increment_rc();
-                    } else {
-                        game_version.increment_pre();
-                    }
-                } else {
-                    if game_version.is_pre() {
-                        game_version.increment_rc();
-                    } else {
-                        break;
-                    }
-                }
-            }
-        }
+    // Created after the game so the clean of the install cannot touch it
+    fs::create_dir_all(mods_path).map_err(|e| {
+        UpdaterError::InvalidPath(format!("Cannot create the mods path {}: {}", mods_path, e))
+    })?;
 
-        if last_version.empty() {
-            LogsInstance::print("No available versions found", colored::Color::BrightRed);
-            Utils::clear_temp(&temp_dir, &working_path);
-            return;
-        }
+    if loaded_arguments.generate_modpack.is_some() {
+        let mod_updater: ModUpdater = ModUpdater::new(loaded_arguments);
+        mod_updater.generate()?;
+
+        let working_path: PathBuf = Utils::working_path(&loaded_arguments.working_path)?;
+        let mut server: ServerLifecycle = ServerLifecycle::new(loaded_arguments, &working_path);
+        let mut hooks: Hooks = Hooks::new(loaded_arguments, &working_path);
+        let mut backup: WorldBackup = WorldBackup::new(loaded_arguments, &working_path);
+        mod_updater.update(&mut server, &mut hooks, &mut backup)?;
+    }
 
-        if last_version.equals(actual_game_version.clone()) {
-            LogsInstance::print("No update needed! :D", colored::Color::BrightGreen);
-            Utils::clear_temp(&temp_dir, &working_path);
-            return;
-        } else if !last_version.bigger_than(actual_game_version.clone()) {
-            LogsInstance::print(
-                "No update needed! :D (Your version is bigger than available versions)",
-                colored::Color::BrightGreen,
-            );
-            Utils::clear_temp(&temp_dir, &working_path);
-            return;
-        }
+    Ok(())
+}
 
-        if last_version.is_pre() || last_version.is_rc() {
-            url_download = format!(
-                "{}unstable/{}{}{}",
-                BASE_URL,
-                game_type,
-                last_version.to_string(),
-                Utils::get_compress_type()
-            );
-        } else {
-            url_download = format!(
-                "{}stable/{}{}{}",
-                BASE_URL,
-                game_type,
-                last_version.to_string(),
-                Utils::get_compress_type()
-            );
-        }
+fn print_mod_list(entries: &[ModListEntry], json: bool) -> Result<(), UpdaterError> {
+    if json {
+        let output: String = serde_json::to_string_pretty(entries)
+            .map_err(|e| UpdaterError::Other(format!("Cannot serialize the mod list: {}", e)))?;
+        println!("{}", output);
+        return Ok(());
     }
 
     LogsInstance::print(
         format!(
-            "All files and folders will be deleted in: {}, except for ignored!!",
-            working_path.display()
+            "{:<24} {:<32} {:<14} {:<10} {:<10} Update",
+            "Modid", "Name", "Version", "File id", "Side"
         )
         .as_str(),
-        colored::Color::BrightYellow,
+        colored::Color::BrightWhite,
     );
 
-    Utils::countdown(5, colored::Color::BrightRed);
+    for entry in entries {
+        let (update, color): (&str, colored::Color) = match entry.update_available {
+            Some(true) => ("available", colored::Color::BrightGreen),
+            Some(false) => ("up to date", colored::Color::White),
+            None if entry.fileid.is_none() => ("untracked", colored::Color::BrightBlack),
+            None => ("unknown", colored::Color::Yellow),
+        };
 
-    match Utils::clean_working_path(&working_path) {
-        Ok(_) => LogsInstance::print("Working path cleared!", colored::Color::Green),
-        Err(e) => {
-            LogsInstance::print(
-                format!("Failed to clean working path: {}", e).as_str(),
-                colored::Color::BrightRed,
-            );
-            return;
-        }
+        LogsInstance::print(
+            format!(
+                "{:<24} {:<32} {:<14} {:<10} {:<10} {}",
+                Utils::truncate(&entry.modid, 24),
+                Utils::truncate(&entry.name, 32),
+                entry.version.as_deref().unwrap_or("-"),
+                entry.fileid.as_deref().unwrap_or("-"),
+                entry.side.as_deref().unwrap_or("-"),
+                update
+            )
+            .as_str(),
+            color,
+        );
     }
 
-    let compressed_version: PathBuf;
-    match Utils::download_file(&url_download, &working_path) {
-        Ok(path) => compressed_version = path,
-        Err(e) => {
-            LogsInstance::print(
-                format!("Failed to download the version: {}", e).as_str(),
-                colored::Color::BrightRed,
-            );
-            return;
-        }
-    }
+    LogsInstance::print(
+        format!("{} mods installed", entries.len()).as_str(),
+        colored::Color::BrightWhite,
+    );
 
-    match Utils::uncompress(&compressed_version) {
-        Ok(_) => {}
-        Err(e) => {
-            LogsInstance::print(
-                format!("Failed to uncompress: {}", e).as_str(),
-                colored::Color::BrightRed,
-            );
-            return;
-        }
-    }
+    Ok(())
+}
 
-    if arch == "arm64" {
-        match Utils::patch_arm64(&working_path, &last_version.to_string()) {
-            Ok(_) => LogsInstance::print("ARM64 binaries applied!", colored::Color::BrightGreen),
-            Err(e) => {
-                LogsInstance::print(
-                    format!("Failed to apply ARM64 patch: {}", e).as_str(),
-                    colored::Color::BrightRed,
-                );
-                return;
-            }
-        }
+/// Newest last, like the file
+fn print_history(
+    history: &History,
+    entries: &[HistoryEntry],
+    json: bool,
+) -> Result<(), UpdaterError> {
+    if json {
+        let output: String = serde_json::to_string_pretty(entries)
+            .map_err(|e| UpdaterError::Other(format!("Cannot serialize the history: {}", e)))?;
+        println!("{}", output);
+        return Ok(());
     }
 
-    LogsInstance::print(
-        "Moving temp files to working path...",
-        colored::Color::White,
-    );
-
-    Utils::clear_temp(&temp_dir, &working_path);
-    let _ = std::fs::remove_file(&compressed_version);
+    if entries.is_empty() {
+        LogsInstance::warn(
+            format!("No matching history in {}", history.path().display()).as_str(),
+            colored::Color::Yellow,
+        );
+        return Ok(());
+    }
 
     LogsInstance::print(
         format!(
-            "Success!!!, your vintage story has been updated to {}",
-            last_version.to_string()
+            "{:<24} {:<11} {:<24} {:<14} {:<14} {:<8} Result",
+            "Date", "Action", "Target", "From", "To", "Time"
         )
         .as_str(),
-        colored::Color::BrightGreen,
+        colored::Color::BrightWhite,
     );
-}
 
-fn update_mods(loaded_arguments: &arguments::Items) {
-    let mods_path: &String = match &loaded_arguments.mods_path {
-        Some(path) => path,
-        None => {
-            LogsInstance::print(
-                "Ignoring mods update because the --mods-path is not set",
-                colored::Color::Yellow,
-            );
-            return;
-        }
-    };
+    for entry in entries {
+        let (result, color): (String, colored::Color) = match &entry.error {
+            None if entry.success => (String::from("ok"), colored::Color::Green),
+            Some(error) => (format!("failed: {}", error), colored::Color::BrightRed),
+            None => (String::from("failed"), colored::Color::BrightRed),
+        };
 
-    let path: &Path = Path::new(mods_path);
-    if !path.is_dir() {
         LogsInstance::print(
-            format!("--mods-path is not valid, {}", path.to_str().unwrap()).as_str(),
-            colored::Color::BrightRed,
+            format!(
+                "{:<24} {:<11} {:<24} {:<14} {:<14} {:<8} {}",
+                Utils::format_datetime(UNIX_EPOCH + Duration::from_secs(entry.timestamp)),
+                entry.action.label(),
+                Utils::truncate(&entry.target, 24),
+                Utils::truncate(entry.from.as_deref().unwrap_or("-"), 14),
+                Utils::truncate(entry.to.as_deref().unwrap_or("-"), 14),
+                format!("{}s", entry.duration_secs),
+                result
+            )
+            .as_str(),
+            color,
         );
-        return;
     }
 
-    let entries: fs::ReadDir = match fs::read_dir(path) {
-        Ok(entries) => entries,
-        Err(err) => {
-            LogsInstance::print(
-                format!("Error reading directory: {}", err).as_str(),
-                colored::Color::BrightRed,
-            );
-            return;
-        }
-    };
-
-    for entry_result in entries {
-        let entry: fs::DirEntry = match entry_result {
-            Ok(e) => e,
-            Err(err) => {
-                LogsInstance::print(
-                    format!("Error reading directory entry: {}", err).as_str(),
-                    colored::Color::BrightRed,
-                );
-                continue;
-            }
-        };
-
-        let path: PathBuf = entry.path();
-
-        if let Some(name) = path.file_name() {
-            LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
-            LogsInstance::print(
-                format!("Updating Mod: {}", name.to_string_lossy()).as_str(),
-                colored::Color::BrightWhite,
-            );
-
-            let (str_id, str_fileid) =
-                match (Utils::get_mod_id(&path), Utils::get_mod_fileid(&path)) {
-                    (Some(id), Some(fileid)) => (id, fileid),
-                    _ => {
-                        LogsInstance::print(
-                            format!("No modid.txt ignoring: {}", name.to_string_lossy()).as_str(),
-                            colored::Color::BrightWhite,
-                        );
-                        LogsInstance::print(
-                            "-----------------------------",
-                            colored::Color::BrightWhite,
-                        );
-                        continue;
-                    }
-                };
-
-            let ping_url: String = format!("{}{}", MODS_URL, str_id);
-
-            LogsInstance::print(
-                format!("Pinging: {}", ping_url).as_str(),
-                colored::Color::BrightWhite,
-            );
-
-            let (biggest_id, biggest_filename) =
-                Utils::get_mod_last_id_name_by_ping_url(&ping_url, loaded_arguments.no_pre_mods);
-
-            let id_download: i64;
-            let filename_download: String;
-            if let Some(id) = biggest_id {
-                match str_fileid.parse::<i64>() {
-                    Ok(actual_id) => {
-                        if actual_id >= id {
-                            LogsInstance::print(
-                                format!("Mod: {} is already on last version", ping_url).as_str(),
-                                colored::Color::Green,
-                            );
-                            LogsInstance::print(
-                                "-----------------------------",
-                                colored::Color::BrightWhite,
-                            );
-                            continue;
-                        }
-                    }
-                    Err(_) => {}
-                }
-                id_download = id;
-            } else {
-                LogsInstance::print(
-                    format!("File id parse failed for: {}", mods_path).as_str(),
-                    colored::Color::BrightRed,
-                );
-                LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
-                continue;
-            }
-
-            if let Some(filename) = biggest_filename {
-                filename_download = filename;
-            } else {
-                LogsInstance::print(
-                    format!("File name parse failed for: {}", mods_path).as_str(),
-                    colored::Color::BrightRed,
-                );
-                LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
-                continue;
-            }
-
-            let url_download: String =
-                format!("{}download/{}/{}", MODS_URL, id_download, filename_download);
-
-            LogsInstance::print(
-                format!("Mod update available: {}", url_download).as_str(),
-                colored::Color::BrightGreen,
-            );
-
-            if !Utils::url_exists(&url_download) {
-                LogsInstance::print(
-                    "No connection or the mod does no longer exist",
-                    colored::Color::BrightRed,
-                );
-                LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
-                continue;
-            }
-
-            match Utils::clean_working_path(&path) {
-                Ok(_) => LogsInstance::print("Mod data removed", colored::Color::Green),
-                Err(e) => {
-                    LogsInstance::print(
-                        format!("Failed to clean mod data: {}", e).as_str(),
-                        colored::Color::BrightRed,
-                    );
-                    LogsInstance::print(
-                        "-----------------------------",
-                        colored::Color::BrightWhite,
-                    );
-                    continue;
-                }
-            }
+    Ok(())
+}
 
-            let compressed_version: PathBuf;
-            match Utils::download_file(&url_download, &path) {
-                Ok(path) => compressed_version = path,
-                Err(e) => {
-                    LogsInstance::print(
-                        format!("Failed to download the version: {}", e).as_str(),
-                        colored::Color::BrightRed,
-                    );
-                    LogsInstance::print(
-                        "-----------------------------",
-                        colored::Color::BrightWhite,
-                    );
-                    continue;
-                }
-            }
+fn backups_list(loaded_arguments: &arguments::Items) -> Result<(), UpdaterError> {
+    let working_path: PathBuf = Utils::working_path(&loaded_arguments.working_path)?;
 
-            LogsInstance::print("File downloaded, decompressing...", colored::Color::White);
-
-            match Utils::uncompress(&compressed_version) {
-                Ok(_) => {}
-                Err(e) => {
-                    LogsInstance::print(
-                        format!("Failed to uncompress: {}", e).as_str(),
-                        colored::Color::BrightRed,
-                    );
-                    LogsInstance::print(
-                        "-----------------------------",
-                        colored::Color::BrightWhite,
-                    );
-                    continue;
-                }
-            }
+    WorldBackup::new(loaded_arguments, &working_path).print_list();
 
-            match fs::File::create(&path.join("modid.txt")) {
-                Ok(mut file) => {
-                    if let Err(_) =
-                        file.write_all(format!("{}\n{}", str_id, id_download).as_bytes())
-                    {
-                        LogsInstance::print(
-                            "Cannot write mod id, no permission maybe.",
-                            colored::Color::BrightRed,
-                        );
-                    }
-                }
-                Err(_) => {
-                    LogsInstance::print(
-                        "Cannot create mod id file you will need to do manually",
-                        colored::Color::BrightRed,
-                    );
-                }
-            }
-
-            match fs::remove_file(&path.join(filename_download)) {
-                Ok(_) => {}
-                Err(_) => {
-                    LogsInstance::print(
-                        "Cannot delete mod update, you will need to delete it manually",
-                        colored::Color::BrightRed,
-                    );
-                }
-            }
+    Ok(())
+}
 
-            let downloaded_version: String = match Utils::get_version_from_modinfo(
-                &path.join("modinfo.json"),
-            ) {
-                Some(ver) => ver,
-                None => {
-                    LogsInstance::print(
-                                format!("Version not found in modinfo.json {}, version text will not be changed", path.display())
-                                    .as_str(),
-                                colored::Color::BrightYellow,
-                            );
-                    LogsInstance::print(
-                        "-----------------------------",
-                        colored::Color::BrightWhite,
-                    );
-                    continue;
-                }
-            };
-
-            let new_path: PathBuf = match Utils::get_updated_path_from_version(
-                &path,
-                name,
-                downloaded_version.as_str(),
-            ) {
-                Some(p) => p,
-                None => {
-                    LogsInstance::print(
-                        format!("Cannot get updated mod name: {}", path.display()).as_str(),
-                        colored::Color::BrightRed,
-                    );
-                    LogsInstance::print(
-                        "-----------------------------",
-                        colored::Color::BrightWhite,
-                    );
-                    continue;
-                }
-            };
+fn backups_restore(loaded_arguments: &arguments::Items, name: &str) -> Result<(), UpdaterError> {
+    let working_path: PathBuf = Utils::working_path(&loaded_arguments.working_path)?;
 
-            match fs::rename(&path, &new_path) {
-                Ok(_) => {
-                    LogsInstance::print("Successfully updated the mod", colored::Color::BrightGreen)
-                }
-                Err(_) => LogsInstance::print(
-                    format!("Cannot rename the mod: {}", path.display()).as_str(),
-                    colored::Color::BrightRed,
-                ),
-            }
+    let mut server: ServerLifecycle = ServerLifecycle::new(loaded_arguments, &working_path);
+    server
+        .stop()
+        .map_err(|e| UpdaterError::Server(format!("Cannot stop the server: {}", e)))?;
 
-            LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
-        }
+    // The server writes the saves while running, restoring under it would corrupt them
+    if !server.is_stopped() && server.find_server_pid().is_some() {
+        return Err(UpdaterError::Server(String::from(
+            "The server is still running, use --stop-server to restore a backup",
+        )));
     }
-}
-
-fn generate_mods(loaded_arguments: &arguments::Items) {
-    let mods_path = match &loaded_arguments.mods_path {
-        Some(path) => path,
-        None => {
-            LogsInstance::print(
-                "Ignoring mods update because the --mods-path is not set",
-                colored::Color::Yellow,
-            );
-            return;
-        }
-    };
 
-    let path: &Path = Path::new(mods_path);
-    if !path.is_dir() {
+    let result: Result<(), UpdaterError> =
+        WorldBackup::new(loaded_arguments, &working_path).restore(name);
+    if result.is_ok() {
         LogsInstance::print(
-            format!("--mods-path is not valid, {}", path.to_str().unwrap()).as_str(),
-            colored::Color::BrightRed,
+            format!("World restored from {}", name).as_str(),
+            colored::Color::BrightGreen,
         );
-        return;
     }
 
-    let id_mods: Vec<String> = loaded_arguments.generate_modpack.clone().unwrap();
-
-    // Generate mod id file
-    for mod_id in &id_mods {
-        let mod_folder: PathBuf = path.join(format!("{}_0.0.0", mod_id));
-        let modid_file: PathBuf = mod_folder.join("modid.txt");
-
-        // Create all necessary folders
-        if let Err(err) = fs::create_dir_all(&mod_folder) {
-            LogsInstance::print(
-                format!("Cannot create folder '{}': {}", mod_folder.display(), err).as_str(),
-                colored::Color::BrightRed,
-            );
-            continue;
-        }
-
-        // Creates the file
-        match fs::File::create(&modid_file) {
-            Ok(mut file) => {
-                if let Err(err) = file.write_all(format!("{}\n{}", mod_id, 0).as_bytes()) {
-                    LogsInstance::print(
-                        format!("Cannot write to modid.txt: {}", err).as_str(),
-                        colored::Color::BrightRed,
-                    );
-                }
-            }
-            Err(err) => {
-                LogsInstance::print(
-                    format!("Cannot create modid.txt: {}", err).as_str(),
-                    colored::Color::BrightRed,
-                );
-            }
-        }
+    if let Err(e) = server.restart() {
+        LogsInstance::error(
+            format!("Failed to restart the server: {}", e).as_str(),
+            colored::Color::BrightRed,
+        );
     }
+
+    result
 }
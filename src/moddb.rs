@@ -0,0 +1,237 @@
+use serde::Deserialize;
+
+use crate::arguments::Channel;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+use crate::version::GameVersion;
+
+const MODS_API_URL: &str = "https://mods.vintagestory.at/api/";
+
+#[derive(Debug, Deserialize)]
+struct ModSearchResponse {
+    #[serde(default)]
+    mods: Vec<ModSearchEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModSearchEntry {
+    pub modid: i64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub downloads: i64,
+    #[serde(default)]
+    pub urlalias: Option<String>,
+    #[serde(default)]
+    pub modidstrs: Vec<String>,
+    #[serde(default)]
+    pub side: Option<String>,
+    /// Date and time of the last release, like 2025-01-31 12:00:00
+    #[serde(default)]
+    pub lastreleased: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModDetailResponse {
+    #[serde(rename = "mod")]
+    mod_detail: ModDetail,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModDetail {
+    #[serde(default)]
+    pub assetid: i64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub urlalias: Option<String>,
+    #[serde(default)]
+    pub side: Option<String>,
+    /// Modids of the modinfo.json of the releases
+    #[serde(default)]
+    pub modidstrs: Vec<String>,
+    #[serde(default)]
+    pub releases: Vec<ModRelease>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModRelease {
+    #[serde(default)]
+    pub fileid: Option<i64>,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub modversion: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl ModDetail {
+    /// Slug used by the mod page url, the same id stored in modid.txt
+    pub fn slug(&self) -> String {
+        match &self.urlalias {
+            Some(alias) if !alias.is_empty() => alias.clone(),
+            _ => format!("show/mod/{}", self.assetid),
+        }
+    }
+}
+
+pub struct ModDb;
+
+impl ModDb {
+    pub fn search(text: &str) -> Result<Vec<ModSearchEntry>, String> {
+        let url: String = format!("{}mods?text={}", MODS_API_URL, Utils::url_encode(text));
+
+        let body: String =
+            Utils::url_result(&url).ok_or_else(|| format!("Failed to query: {}", url))?;
+
+        let response: ModSearchResponse = serde_json::from_str(&body)
+            .map_err(|e| format!("Invalid mod search response: {}", e))?;
+
+        Ok(response.mods)
+    }
+
+    pub fn get_mod(id: &str) -> Result<ModDetail, String> {
+        let url: String = format!("{}mod/{}", MODS_API_URL, Utils::url_encode(id));
+
+        let body: String =
+            Utils::url_result(&url).ok_or_else(|| format!("Failed to query: {}", url))?;
+
+        let response: ModDetailResponse =
+            serde_json::from_str(&body).map_err(|e| format!("Invalid mod response: {}", e))?;
+
+        Ok(response.mod_detail)
+    }
+
+    /// Finds a mod by the slug from the mod page url, falling back to the search when
+    /// the slug is not the same as the mod id string
+    pub fn get_mod_by_slug(slug: &str) -> Result<ModDetail, String> {
+        if let Ok(detail) = ModDb::get_mod(slug) {
+            return Ok(detail);
+        }
+
+        let results: Vec<ModSearchEntry> = ModDb::search(slug)?;
+        let entry: &ModSearchEntry = results
+            .iter()
+            .find(|entry| {
+                entry.urlalias.as_deref() == Some(slug)
+                    || entry
+                        .modidstrs
+                        .iter()
+                        .any(|id| id.eq_ignore_ascii_case(slug))
+            })
+            .ok_or_else(|| format!("Mod not found: {}", slug))?;
+
+        ModDb::get_mod(&entry.modid.to_string())
+    }
+
+    /// Newest release for the game version, releases tagged with the exact version are
+    /// preferred, then any release from the same minor version
+    pub fn pick_release<'a>(
+        releases: &'a [ModRelease],
+        game_version: Option<&GameVersion>,
+        channel: Channel,
+    ) -> Option<&'a ModRelease> {
+        let candidates: Vec<&ModRelease> = releases
+            .iter()
+            .filter(|release| release.fileid.is_some() && release.filename.is_some())
+            .filter(|release| channel.accepts_label(release.modversion.as_deref().unwrap_or("")))
+            .collect();
+
+        let newest = |filter: &dyn Fn(&ModRelease) -> bool| -> Option<&'a ModRelease> {
+            candidates
+                .iter()
+                .filter(|release| filter(release))
+                .max_by_key(|release| release.fileid)
+                .copied()
+        };
+
+        let version: &GameVersion = match game_version {
+            Some(version) => version,
+            None => return newest(&|_| true),
+        };
+
+        let exact_tag: String = format!("v{}", version);
+        let minor_tag: String = format!("v{}.{}.", version.major(), version.minor());
+
+        newest(&|release| release.tags.iter().any(|tag| tag == &exact_tag)).or_else(|| {
+            newest(&|release| release.tags.iter().any(|tag| tag.starts_with(&minor_tag)))
+        })
+    }
+
+    /// Prints the results with the fields of the search, versions also fetches the details of
+    /// each shown mod for its latest release
+    pub fn print_search_results(results: &[ModSearchEntry], limit: usize, versions: bool) {
+        if results.is_empty() {
+            LogsInstance::warn("No mods found", colored::Color::Yellow);
+            return;
+        }
+
+        let mut header: String = format!(
+            "{:<32} {:<24} {:<20} {:>10} {:<8} {:<12}",
+            "Name", "Slug", "Author", "Downloads", "Side", "Released"
+        );
+        if versions {
+            header.push_str(&format!(" {:<12} Game versions", "Latest"));
+        }
+        LogsInstance::print(header.as_str(), colored::Color::BrightWhite);
+
+        for entry in results.iter().take(limit) {
+            let slug: String = entry
+                .urlalias
+                .clone()
+                .filter(|alias| !alias.is_empty())
+                .or_else(|| entry.modidstrs.first().cloned())
+                .unwrap_or_default();
+
+            let mut line: String = format!(
+                "{:<32} {:<24} {:<20} {:>10} {:<8} {:<12}",
+                Utils::truncate(&entry.name, 32),
+                Utils::truncate(&slug, 24),
+                Utils::truncate(&entry.author, 20),
+                entry.downloads,
+                entry.side.as_deref().unwrap_or("-"),
+                entry
+                    .lastreleased
+                    .as_deref()
+                    .and_then(|date| date.split_whitespace().next())
+                    .unwrap_or("-")
+            );
+
+            // The search does not return releases, so the details are needed for the versions
+            if versions {
+                let (latest, tags): (String, String) =
+                    match ModDb::get_mod(&entry.modid.to_string()) {
+                        Ok(detail) => {
+                            match detail.releases.iter().max_by_key(|release| release.fileid) {
+                                Some(release) => (
+                                    release.modversion.clone().unwrap_or_default(),
+                                    release.tags.join(","),
+                                ),
+                                None => (String::from("-"), String::new()),
+                            }
+                        }
+                        Err(_) => (String::from("?"), String::new()),
+                    };
+                line.push_str(&format!(" {:<12} {}", latest, tags));
+            }
+
+            LogsInstance::print(line.as_str(), colored::Color::White);
+        }
+
+        if results.len() > limit {
+            LogsInstance::print(
+                format!(
+                    "{} more results, use --limit to show them",
+                    results.len() - limit
+                )
+                .as_str(),
+                colored::Color::BrightBlack,
+            );
+        }
+    }
+}
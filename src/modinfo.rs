@@ -0,0 +1,235 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::state::InstallState;
+use crate::utils::Utils;
+
+/// Dependencies provided by the game itself
+const BUILTIN_MODS: [&str; 3] = ["game", "survival", "creative"];
+
+#[derive(Debug, Clone, Default)]
+pub struct ModInfo {
+    pub modid: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub side: Option<String>,
+    pub dependencies: Vec<String>,
+}
+
+impl ModInfo {
+    /// Reads the modinfo.json from a folder or zip mod
+    pub fn from_path(mod_path: &Path) -> Option<ModInfo> {
+        let contents: String = if mod_path.is_dir() {
+            fs::read_to_string(mod_path.join("modinfo.json")).ok()?
+        } else if mod_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+        {
+            Utils::read_zip_entry(mod_path, "modinfo.json")?
+        } else {
+            return None;
+        };
+
+        ModInfo::parse(&contents)
+    }
+
+    /// modinfo.json files are not always strict json (comments, trailing commas, any key
+    /// casing), so only the needed values are extracted
+    pub fn parse(contents: &str) -> Option<ModInfo> {
+        let name: String = ModInfo::get_string(contents, "name")?;
+
+        // The game generates the mod id from the name when it is missing
+        let modid: String = ModInfo::get_string(contents, "modid").unwrap_or_else(|| {
+            name.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        });
+
+        Some(ModInfo {
+            modid,
+            name,
+            version: ModInfo::get_string(contents, "version"),
+            side: ModInfo::get_string(contents, "side"),
+            dependencies: ModInfo::get_dependencies(contents),
+        })
+    }
+
+    fn get_string(contents: &str, key: &str) -> Option<String> {
+        let re: Regex = Regex::new(&format!(r#"(?i)"{}"\s*:\s*"([^"]*)""#, key)).ok()?;
+        re.captures(contents)
+            .and_then(|cap| cap.get(1))
+            .map(|m| m.as_str().to_string())
+    }
+
+    fn get_dependencies(contents: &str) -> Vec<String> {
+        let block_re: Regex = Regex::new(r#"(?is)"dependencies"\s*:\s*\{([^}]*)\}"#).unwrap();
+        let key_re: Regex = Regex::new(r#""([^"]+)"\s*:"#).unwrap();
+
+        let block: &str = match block_re.captures(contents).and_then(|cap| cap.get(1)) {
+            Some(m) => m.as_str(),
+            None => return Vec::new(),
+        };
+
+        key_re
+            .captures_iter(block)
+            .filter_map(|cap| cap.get(1))
+            .map(|m| m.as_str().to_lowercase())
+            .filter(|id| !BUILTIN_MODS.contains(&id.as_str()))
+            .collect()
+    }
+
+    pub fn depends_on(&self, modid: &str) -> bool {
+        self.dependencies
+            .iter()
+            .any(|dependency| dependency.eq_ignore_ascii_case(modid))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstalledMod {
+    pub path: PathBuf,
+    pub info: Option<ModInfo>,
+    /// Mod page id and file id from the state
+    pub tracked_id: Option<String>,
+    pub fileid: Option<String>,
+}
+
+impl InstalledMod {
+    pub fn scan(mods_path: &Path, state: &InstallState) -> Vec<InstalledMod> {
+        let mut installed: Vec<InstalledMod> = Vec::new();
+
+        let entries: fs::ReadDir = match fs::read_dir(mods_path) {
+            Ok(entries) => entries,
+            Err(_) => return installed,
+        };
+
+        for entry in entries.flatten() {
+            let path: PathBuf = entry.path();
+
+            // Hidden entries are tool data like the .backup fallback folder
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let (tracked_id, fileid): (Option<String>, Option<String>) =
+                match state.mod_by_folder(&entry.file_name().to_string_lossy()) {
+                    Some((id, tracked)) => (Some(id.clone()), Some(tracked.fileid.to_string())),
+                    None => (None, None),
+                };
+
+            let info: Option<ModInfo> = ModInfo::from_path(&path);
+
+            if info.is_none() && tracked_id.is_none() {
+                continue;
+            }
+
+            installed.push(InstalledMod {
+                path,
+                info,
+                tracked_id,
+                fileid,
+            });
+        }
+
+        installed.sort_by(|a, b| a.path.cmp(&b.path));
+        installed
+    }
+
+    /// Matches the modid from modinfo.json or the mod page id from the state
+    pub fn matches(&self, modid: &str) -> bool {
+        self.info
+            .as_ref()
+            .is_some_and(|info| info.modid.eq_ignore_ascii_case(modid))
+            || self
+                .tracked_id
+                .as_deref()
+                .is_some_and(|id| id.eq_ignore_ascii_case(modid))
+    }
+
+    pub fn modid(&self) -> String {
+        match &self.info {
+            Some(info) => info.modid.clone(),
+            None => self.tracked_id.clone().unwrap_or_default(),
+        }
+    }
+
+    pub fn depends_on(&self, modid: &str) -> bool {
+        self.info
+            .as_ref()
+            .is_some_and(|info| info.depends_on(modid))
+    }
+
+    pub fn display_name(&self) -> String {
+        match &self.info {
+            Some(info) => format!("{} ({})", info.name, self.file_name()),
+            None => self.file_name(),
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lenient_json() {
+        let info: ModInfo = ModInfo::parse(
+            r#"{
+                // Comments and trailing commas are allowed by the game
+                "Type": "code",
+                "Name": "Better Ruins",
+                "ModID": "betterruins",
+                "Version": "0.4.2",
+                "Side": "Universal",
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.modid, "betterruins");
+        assert_eq!(info.name, "Better Ruins");
+        assert_eq!(info.version.as_deref(), Some("0.4.2"));
+        assert_eq!(info.side.as_deref(), Some("Universal"));
+        assert!(info.dependencies.is_empty());
+    }
+
+    #[test]
+    fn modid_from_name() {
+        let info: ModInfo = ModInfo::parse(r#"{ "name": "Carry On: Extra!" }"#).unwrap();
+        assert_eq!(info.modid, "carryonextra");
+
+        assert!(ModInfo::parse(r#"{ "modid": "noname" }"#).is_none());
+    }
+
+    #[test]
+    fn dependencies_skip_game_mods() {
+        let info: ModInfo = ModInfo::parse(
+            r#"{
+                "name": "Expanded Foods",
+                "dependencies": {
+                    "game": "1.20.0",
+                    "Survival": "",
+                    "creative": "*",
+                    "ACulinaryArtillery": "1.2.0",
+                    "configlib": ""
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.dependencies, vec!["aculinaryartillery", "configlib"]);
+        assert!(info.depends_on("aCulinaryArtillery"));
+        assert!(!info.depends_on("game"));
+    }
+}
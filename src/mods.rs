@@ -0,0 +1,1070 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::Serialize;
+
+use crate::arguments;
+use crate::backups::WorldBackup;
+use crate::error::UpdaterError;
+use crate::history::{History, HistoryAction, HistoryEntry};
+use crate::hooks::{Hook, Hooks};
+use crate::logger::LogsInstance;
+use crate::moddb::{ModDb, ModDetail, ModRelease, ModSearchEntry};
+use crate::modinfo::{InstalledMod, ModInfo};
+use crate::server::ServerLifecycle;
+use crate::state::InstallState;
+use crate::utils::Utils;
+use crate::version::GameVersion;
+
+pub fn get_backup_path(mods_path: &Path, loaded_arguments: &arguments::Items) -> PathBuf {
+    if let Some(path) = &loaded_arguments.mods_backup_path {
+        return PathBuf::from(path);
+    }
+
+    // Backups must stay outside the mods folder, otherwise the game tries to load them
+    match mods_path.parent() {
+        Some(parent) if parent != Path::new("") => parent.join("ModsBackup"),
+        _ => mods_path.join(".backup"),
+    }
+}
+
+pub fn backup_mod(
+    mod_path: &Path,
+    backup_path: &Path,
+    mod_id: &str,
+) -> Result<PathBuf, UpdaterError> {
+    let mod_backup_dir: PathBuf = backup_path.join(mod_id);
+    let file_name: &OsStr = mod_path.file_name().ok_or_else(|| {
+        UpdaterError::InvalidPath(format!("Invalid mod path {}", mod_path.display()))
+    })?;
+
+    // Only the last backup is kept for each mod
+    if mod_backup_dir.exists() {
+        fs::remove_dir_all(&mod_backup_dir)
+            .map_err(|e| UpdaterError::Backup(format!("Cannot remove old backup: {}", e)))?;
+    }
+
+    fs::create_dir_all(&mod_backup_dir)
+        .map_err(|e| UpdaterError::Backup(format!("Cannot create backup folder: {}", e)))?;
+
+    Utils::move_item(mod_path, &mod_backup_dir)
+        .map_err(|e| UpdaterError::Backup(format!("Cannot move mod to backup: {}", e)))?;
+
+    Ok(mod_backup_dir.join(file_name))
+}
+
+pub fn get_backup_entry(backup_path: &Path, mod_id: &str) -> Option<PathBuf> {
+    let mod_backup_dir: PathBuf = backup_path.join(mod_id);
+
+    fs::read_dir(&mod_backup_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .next()
+}
+
+pub fn restore_mod(
+    mod_path: &Path,
+    backup_path: &Path,
+    mod_id: &str,
+) -> Result<PathBuf, UpdaterError> {
+    let backup_entry: PathBuf = get_backup_entry(backup_path, mod_id)
+        .ok_or_else(|| UpdaterError::Backup(format!("No backup found for {}", mod_id)))?;
+
+    let mods_path: &Path = mod_path.parent().ok_or_else(|| {
+        UpdaterError::InvalidPath(format!("Invalid mod path {}", mod_path.display()))
+    })?;
+    let file_name: &OsStr = backup_entry.file_name().ok_or_else(|| {
+        UpdaterError::InvalidPath(format!("Invalid backup path {}", backup_entry.display()))
+    })?;
+
+    // Remove any leftover from the failed update
+    let removed: std::io::Result<()> = if mod_path.is_dir() {
+        fs::remove_dir_all(mod_path)
+    } else if mod_path.exists() {
+        fs::remove_file(mod_path)
+    } else {
+        Ok(())
+    };
+    removed.map_err(|e| UpdaterError::Backup(format!("Cannot remove failed update: {}", e)))?;
+
+    Utils::move_item(&backup_entry, mods_path)
+        .map_err(|e| UpdaterError::Backup(format!("Cannot move backup to mods path: {}", e)))?;
+
+    let _ = fs::remove_dir_all(backup_path.join(mod_id));
+
+    Ok(mods_path.join(file_name))
+}
+
+pub fn install_mod_update(
+    path: &Path,
+    name: &OsStr,
+    url_download: &str,
+    filename_download: &str,
+    str_id: &str,
+    id_download: i64,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(path).map_err(|e| format!("Cannot create mod folder: {}", e))?;
+
+    let compressed_version: PathBuf = Utils::download_file(url_download, path)
+        .map_err(|e| format!("Failed to download the version: {}", e))?;
+
+    LogsInstance::print("File downloaded, decompressing...", colored::Color::White);
+
+    Utils::uncompress(&compressed_version, None)
+        .map_err(|e| format!("Failed to uncompress: {}", e))?;
+
+    let mut file: fs::File = fs::File::create(path.join("modid.txt"))
+        .map_err(|e| format!("Cannot create mod id file: {}", e))?;
+    file.write_all(format!("{}\n{}", str_id, id_download).as_bytes())
+        .map_err(|e| format!("Cannot write mod id: {}", e))?;
+
+    if fs::remove_file(path.join(filename_download)).is_err() {
+        LogsInstance::error(
+            "Cannot delete mod update, you will need to delete it manually",
+            colored::Color::BrightRed,
+        );
+    }
+
+    let downloaded_version: String =
+        match Utils::get_version_from_modinfo(&path.join("modinfo.json")) {
+            Some(ver) => ver,
+            None => {
+                LogsInstance::warn(
+                    format!(
+                        "Version not found in modinfo.json {}, version text will not be changed",
+                        path.display()
+                    )
+                    .as_str(),
+                    colored::Color::BrightYellow,
+                );
+                return Ok(path.to_path_buf());
+            }
+        };
+
+    let new_path: PathBuf =
+        Utils::get_updated_path_from_version(path, name, downloaded_version.as_str())
+            .ok_or_else(|| format!("Cannot get updated mod name: {}", path.display()))?;
+
+    fs::rename(path, &new_path)
+        .map_err(|e| format!("Cannot rename the mod: {}: {}", path.display(), e))?;
+
+    Ok(new_path)
+}
+
+/// Version from modinfo.json, or the one in the folder name like carryon_1.8.0
+pub fn installed_version(mod_path: &Path) -> Option<String> {
+    ModInfo::from_path(mod_path)
+        .and_then(|info| info.version)
+        .or_else(|| {
+            mod_path
+                .file_name()?
+                .to_string_lossy()
+                .trim_end_matches(".zip")
+                .rsplit_once('_')
+                .map(|(_, version)| version.to_string())
+        })
+}
+
+/// Side from modinfo.json, or from the mod database when the mod is not downloaded yet
+pub fn get_mod_side(mod_path: &Path, mod_id: &str) -> Option<String> {
+    if let Some(side) = ModInfo::from_path(mod_path).and_then(|info| info.side) {
+        return Some(side);
+    }
+
+    ModDb::get_mod_by_slug(mod_id)
+        .ok()
+        .and_then(|detail| detail.side)
+}
+
+/// Applies --side-filter to a mod, returns true when the mod must be skipped
+pub fn skip_by_side(
+    loaded_arguments: &arguments::Items,
+    mod_name: &str,
+    side: Option<String>,
+) -> bool {
+    if loaded_arguments.side_filter == arguments::SideFilter::Off {
+        return false;
+    }
+
+    let side: String = match side {
+        Some(side) => side.to_lowercase(),
+        None => return false,
+    };
+
+    // Universal mods are reported as "both" by the mod database
+    let game_type: &str = loaded_arguments.game_type.as_deref().unwrap_or("server");
+    let unwanted_side: &str = if game_type == "client" {
+        "server"
+    } else {
+        "client"
+    };
+
+    if side != unwanted_side {
+        return false;
+    }
+
+    match loaded_arguments.side_filter {
+        arguments::SideFilter::Skip => {
+            LogsInstance::warn(
+                format!("Skipping {}, it is a {} only mod", mod_name, side).as_str(),
+                colored::Color::Yellow,
+            );
+            true
+        }
+        _ => {
+            LogsInstance::warn(
+                format!(
+                    "{} is a {} only mod and is not needed for a {}",
+                    mod_name, side, game_type
+                )
+                .as_str(),
+                colored::Color::BrightYellow,
+            );
+            false
+        }
+    }
+}
+
+pub fn find_installed_mod(mods_path: &Path, state: &InstallState, mod_id: &str) -> Option<PathBuf> {
+    let path: PathBuf = mods_path.join(&state.mods.get(mod_id)?.folder);
+    path.exists().then_some(path)
+}
+
+/// The --mods-path of a mod command, the action is named when it is not set
+fn mods_dir(loaded_arguments: &arguments::Items, action: &str) -> Result<PathBuf, UpdaterError> {
+    let mods_path: PathBuf = match &loaded_arguments.mods_path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            return Err(UpdaterError::InvalidPath(format!(
+                "Cannot {} because the --mods-path is not set",
+                action
+            )));
+        }
+    };
+
+    if !mods_path.is_dir() {
+        return Err(UpdaterError::InvalidPath(format!(
+            "--mods-path is not valid, {}",
+            mods_path.display()
+        )));
+    }
+
+    Ok(mods_path)
+}
+
+pub fn revert_mod(loaded_arguments: &arguments::Items, mod_id: &str) -> Result<(), UpdaterError> {
+    let mods_path: PathBuf = mods_dir(loaded_arguments, "revert the mod")?;
+
+    let backup_path: PathBuf = get_backup_path(&mods_path, loaded_arguments);
+
+    let backup_entry: PathBuf = get_backup_entry(&backup_path, mod_id).ok_or_else(|| {
+        UpdaterError::Backup(format!(
+            "No backup found for {} in {}",
+            mod_id,
+            backup_path.display()
+        ))
+    })?;
+
+    // Swap the backup with the installed version so the revert can be undone
+    let swap_dir: PathBuf = backup_path.join(format!(".{}_swap", mod_id));
+    fs::create_dir_all(&swap_dir)
+        .map_err(|e| UpdaterError::Backup(format!("Cannot create swap folder: {}", e)))?;
+
+    let started: Instant = Instant::now();
+    let mut entry: HistoryEntry = HistoryEntry::new(HistoryAction::ModRevert, mod_id);
+    entry.to = installed_version(&backup_entry);
+
+    let mut state: InstallState = InstallState::from_arguments(loaded_arguments)?;
+    let history: History = History::from_arguments(loaded_arguments)?;
+
+    if let Err(e) = Utils::move_item(&backup_entry, &swap_dir) {
+        let _ = fs::remove_dir(&swap_dir);
+        return Err(UpdaterError::Backup(format!("Cannot move backup: {}", e)));
+    }
+    let swapped: PathBuf = swap_dir.join(backup_entry.file_name().unwrap_or_default());
+    let mod_backup_dir: PathBuf = backup_path.join(mod_id);
+
+    // Puts the backup back in place, the swap folder is only removed once it is empty
+    let undo_swap = || -> String {
+        match Utils::move_item(&swapped, &mod_backup_dir) {
+            Ok(_) => {
+                let _ = fs::remove_dir(&swap_dir);
+                String::from("the backup was moved back")
+            }
+            Err(e) => format!("the backup is kept in {}: {}", swap_dir.display(), e),
+        }
+    };
+
+    let mut moved_installed: Option<PathBuf> = None;
+    if let Some(installed) = find_installed_mod(&mods_path, &state, mod_id) {
+        entry.from = installed_version(&installed);
+        if let Err(e) = Utils::move_item(&installed, &mod_backup_dir) {
+            return Err(UpdaterError::Backup(format!(
+                "Cannot move installed mod to backup: {}, {}",
+                e,
+                undo_swap()
+            )));
+        }
+        LogsInstance::print(
+            format!("Installed version moved to backup: {}", installed.display()).as_str(),
+            colored::Color::White,
+        );
+        moved_installed = Some(mod_backup_dir.join(installed.file_name().unwrap_or_default()));
+    }
+
+    let result: Result<(), String> =
+        Utils::move_item(&swapped, &mods_path).map_err(|e| e.to_string());
+    history.record(entry, &result, started);
+
+    if let Err(e) = result {
+        // The installed version goes back first, the backup folder holds a single entry
+        let restored: String = match &moved_installed {
+            Some(moved) => match Utils::move_item(moved, &mods_path) {
+                Ok(_) => String::from("the installed version was put back"),
+                Err(e) => format!(
+                    "the installed version is kept in {}: {}",
+                    moved.display(),
+                    e
+                ),
+            },
+            None => String::from("nothing was installed"),
+        };
+        return Err(UpdaterError::Backup(format!(
+            "Cannot move backup to mods path: {}, {}, {}",
+            e,
+            restored,
+            undo_swap()
+        )));
+    }
+
+    let _ = fs::remove_dir(&swap_dir);
+    // Only succeeds when there was no installed version to keep
+    let _ = fs::remove_dir(&mod_backup_dir);
+
+    // The modid.txt of the backup has the file id of the reverted version
+    let reverted: PathBuf = mods_path.join(backup_entry.file_name().unwrap_or_default());
+    match InstallState::read_modid_file(&reverted) {
+        (_, Some(fileid)) => state.set_mod(mod_id, &reverted, fileid.parse().unwrap_or(0)),
+        _ => state.set_mod(mod_id, &reverted, 0),
+    }
+    state.save_or_warn();
+
+    LogsInstance::print(
+        format!(
+            "Mod {} reverted to {}",
+            mod_id,
+            backup_entry
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        )
+        .as_str(),
+        colored::Color::BrightGreen,
+    );
+
+    Ok(())
+}
+
+pub fn search_mods(text: &str, limit: usize, versions: bool) -> Result<(), UpdaterError> {
+    LogsInstance::print(
+        format!("Searching mods: {}", text).as_str(),
+        colored::Color::White,
+    );
+
+    let results: Vec<ModSearchEntry> = ModDb::search(text).map_err(UpdaterError::Network)?;
+
+    ModDb::print_search_results(&results, limit, versions);
+
+    Ok(())
+}
+
+pub fn add_mod(loaded_arguments: &arguments::Items, slug: &str) -> Result<(), UpdaterError> {
+    let mods_path: PathBuf = mods_dir(loaded_arguments, "add the mod")?;
+
+    let detail: ModDetail = ModDb::get_mod_by_slug(slug).map_err(UpdaterError::Network)?;
+
+    let mod_slug: String = detail.slug();
+
+    if skip_by_side(loaded_arguments, &detail.name, detail.side.clone()) {
+        return Ok(());
+    }
+
+    let mut state: InstallState = InstallState::from_arguments(loaded_arguments)?;
+
+    // A mod installed by hand has no state entry, the game would load both copies
+    let installed: Option<PathBuf> =
+        find_installed_mod(&mods_path, &state, &mod_slug).or_else(|| {
+            InstalledMod::scan(&mods_path, &state)
+                .into_iter()
+                .find(|installed| detail.modidstrs.iter().any(|id| installed.matches(id)))
+                .map(|installed| installed.path)
+        });
+
+    if let Some(installed) = installed {
+        LogsInstance::warn(
+            format!(
+                "{} is already installed: {}",
+                detail.name,
+                installed.display()
+            )
+            .as_str(),
+            colored::Color::Yellow,
+        );
+        return Ok(());
+    }
+
+    let game_version: Option<GameVersion> =
+        Utils::get_game_version(&Utils::working_path(&loaded_arguments.working_path)?);
+
+    match &game_version {
+        Some(version) => LogsInstance::print(
+            format!("Game version: {}", version).as_str(),
+            colored::Color::White,
+        ),
+        None => LogsInstance::warn(
+            "Unknown game version, the newest release will be installed",
+            colored::Color::Yellow,
+        ),
+    }
+
+    let release: &ModRelease = ModDb::pick_release(
+        &detail.releases,
+        game_version.as_ref(),
+        loaded_arguments.mod_channel(slug),
+    )
+    .ok_or_else(|| {
+        UpdaterError::Version(format!("No compatible release found for {}", detail.name))
+    })?;
+
+    let (fileid, filename): (i64, &str) = match (release.fileid, release.filename.as_deref()) {
+        (Some(fileid), Some(filename)) => (fileid, filename),
+        _ => {
+            return Err(UpdaterError::Mod(format!(
+                "The release {} of {} has no file to download, nothing was installed",
+                release.modversion.as_deref().unwrap_or("?"),
+                detail.name
+            )));
+        }
+    };
+
+    if !filename.to_lowercase().ends_with(".zip") {
+        return Err(UpdaterError::Unsupported(format!(
+            "vs_updater only supports zip mods, {} must be installed manually",
+            filename
+        )));
+    }
+
+    let folder_name: String = format!(
+        "{}_{}",
+        slug.rsplit('/').next().unwrap_or(slug),
+        release.modversion.as_deref().unwrap_or("0.0.0")
+    );
+    let mod_path: PathBuf = mods_path.join(&folder_name);
+    let url_download: String = format!("{}download/{}/{}", crate::MODS_URL, fileid, filename);
+
+    LogsInstance::print(
+        format!(
+            "Installing {} by {}: {}",
+            detail.name, detail.author, url_download
+        )
+        .as_str(),
+        colored::Color::BrightWhite,
+    );
+
+    let started: Instant = Instant::now();
+    let result: Result<PathBuf, String> = install_mod_update(
+        &mod_path,
+        OsStr::new(&folder_name),
+        &url_download,
+        filename,
+        &mod_slug,
+        fileid,
+    );
+
+    let mut entry: HistoryEntry = HistoryEntry::new(HistoryAction::ModAdd, &mod_slug);
+    entry.to = match &result {
+        Ok(path) => installed_version(path),
+        Err(_) => release.modversion.clone(),
+    };
+    entry.url = Some(url_download.clone());
+    History::from_arguments(loaded_arguments)?.record(entry, &result, started);
+
+    match result {
+        Ok(path) => {
+            LogsInstance::print(
+                format!("Mod installed: {}", path.display()).as_str(),
+                colored::Color::BrightGreen,
+            );
+            state.set_mod(&mod_slug, &path, fileid);
+            state.save_or_warn();
+            Ok(())
+        }
+        Err(e) => {
+            let _ = fs::remove_dir_all(&mod_path);
+            Err(UpdaterError::Mod(format!(
+                "Failed to install the mod: {}",
+                e
+            )))
+        }
+    }
+}
+
+pub fn remove_mod(
+    loaded_arguments: &arguments::Items,
+    mod_id: &str,
+    force: bool,
+    remove_orphans: bool,
+) -> Result<(), UpdaterError> {
+    let mods_path: PathBuf = mods_dir(loaded_arguments, "remove the mod")?;
+
+    let mut state: InstallState = InstallState::from_arguments(loaded_arguments)?;
+    let mut installed: Vec<InstalledMod> = InstalledMod::scan(&mods_path, &state);
+
+    let target: InstalledMod = match installed.iter().position(|m| m.matches(mod_id)) {
+        Some(index) => installed.remove(index),
+        None => {
+            return Err(UpdaterError::Mod(format!(
+                "Mod {} is not installed in {}",
+                mod_id,
+                mods_path.display()
+            )));
+        }
+    };
+
+    let target_modid: String = target.modid();
+
+    let dependents: Vec<&InstalledMod> = installed
+        .iter()
+        .filter(|m| m.depends_on(&target_modid))
+        .collect();
+
+    if !dependents.is_empty() {
+        LogsInstance::warn(
+            format!("The following mods depend on {}:", target_modid).as_str(),
+            colored::Color::BrightYellow,
+        );
+        for dependent in &dependents {
+            LogsInstance::warn(
+                format!("  {}", dependent.display_name()).as_str(),
+                colored::Color::BrightYellow,
+            );
+        }
+
+        if !force {
+            return Err(UpdaterError::Mod(String::from(
+                "Nothing removed, use --force to remove it anyway",
+            )));
+        }
+    }
+
+    let mut to_remove: Vec<InstalledMod> = vec![target];
+
+    // Dependencies that no remaining mod needs anymore, checked again after each removal
+    // so dependencies of dependencies are also found
+    if remove_orphans {
+        let mut index: usize = 0;
+        while index < to_remove.len() {
+            let dependencies: Vec<String> = to_remove[index]
+                .info
+                .as_ref()
+                .map(|info| info.dependencies.clone())
+                .unwrap_or_default();
+
+            for dependency in dependencies {
+                let position: Option<usize> = installed.iter().position(|m| m.matches(&dependency));
+                if let Some(position) = position {
+                    let still_needed: bool = installed
+                        .iter()
+                        .enumerate()
+                        .any(|(i, m)| i != position && m.depends_on(&dependency));
+
+                    if !still_needed {
+                        to_remove.push(installed.remove(position));
+                    }
+                }
+            }
+
+            index += 1;
+        }
+    }
+
+    let history: History = History::from_arguments(loaded_arguments)?;
+    let mut failed: Vec<String> = Vec::new();
+
+    for removed in &to_remove {
+        let started: Instant = Instant::now();
+        let mut entry: HistoryEntry = HistoryEntry::new(
+            HistoryAction::ModRemove,
+            &removed
+                .tracked_id
+                .clone()
+                .unwrap_or_else(|| removed.modid()),
+        );
+        entry.from = installed_version(&removed.path);
+
+        let result: std::io::Result<()> = if removed.path.is_dir() {
+            fs::remove_dir_all(&removed.path)
+        } else {
+            fs::remove_file(&removed.path)
+        };
+        history.record(entry, &result.as_ref().map_err(|e| e.to_string()), started);
+
+        match result {
+            Ok(_) => {
+                LogsInstance::print(
+                    format!("Removed: {}", removed.display_name()).as_str(),
+                    colored::Color::BrightGreen,
+                );
+                state.remove_mod(&removed.file_name());
+            }
+            Err(e) => failed.push(format!("{}: {}", removed.path.display(), e)),
+        }
+    }
+
+    state.save_or_warn();
+
+    if !failed.is_empty() {
+        return Err(UpdaterError::Mod(format!(
+            "Cannot remove {}",
+            failed.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModListEntry {
+    pub modid: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub fileid: Option<String>,
+    pub side: Option<String>,
+    pub path: String,
+    /// None when the mod is not tracked or the mod page cannot be reached
+    pub update_available: Option<bool>,
+    pub latest_fileid: Option<i64>,
+}
+
+/// Installed mods with their update status, offline skips the mod pages
+pub fn list_mods(
+    loaded_arguments: &arguments::Items,
+    offline: bool,
+) -> Result<Vec<ModListEntry>, UpdaterError> {
+    let mods_path: PathBuf = mods_dir(loaded_arguments, "list the mods")?;
+
+    let mut entries: Vec<ModListEntry> = Vec::new();
+
+    let state: InstallState = InstallState::from_arguments(loaded_arguments)?;
+
+    for installed in InstalledMod::scan(&mods_path, &state) {
+        let mut latest_fileid: Option<i64> = None;
+        let mut update_available: Option<bool> = None;
+
+        if let (Some(tracked_id), false) = (&installed.tracked_id, offline) {
+            let ping_url: String = format!("{}{}", crate::MODS_URL, tracked_id);
+            let (biggest_id, _) = Utils::get_mod_last_id_name_by_ping_url(
+                &ping_url,
+                loaded_arguments.mod_channel(tracked_id),
+            );
+
+            latest_fileid = biggest_id;
+            let current_fileid: i64 = installed
+                .fileid
+                .as_deref()
+                .and_then(|id| id.parse::<i64>().ok())
+                .unwrap_or(0);
+            update_available = biggest_id.map(|id| id > current_fileid);
+        }
+
+        entries.push(ModListEntry {
+            modid: installed.modid(),
+            name: installed
+                .info
+                .as_ref()
+                .map(|info| info.name.clone())
+                .unwrap_or_else(|| installed.file_name()),
+            version: installed
+                .info
+                .as_ref()
+                .and_then(|info| info.version.clone()),
+            fileid: installed.fileid.clone(),
+            side: installed.info.as_ref().and_then(|info| info.side.clone()),
+            path: installed.path.display().to_string(),
+            update_available,
+            latest_fileid,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Updates the mods of the mods path of the arguments
+pub struct ModUpdater<'a> {
+    arguments: &'a arguments::Items,
+}
+
+impl<'a> ModUpdater<'a> {
+    pub fn new(loaded_arguments: &'a arguments::Items) -> ModUpdater<'a> {
+        ModUpdater {
+            arguments: loaded_arguments,
+        }
+    }
+
+    /// Updates every tracked mod of the mods path to its last release, a mod that fails is
+    /// restored and listed in the failed mods of the hooks, the other mods still update,
+    /// the post-mods hook runs at the end
+    pub fn update(
+        &self,
+        server: &mut ServerLifecycle,
+        hooks: &mut Hooks,
+        world_backup: &mut WorldBackup,
+    ) -> Result<(), UpdaterError> {
+        let loaded_arguments: &arguments::Items = self.arguments;
+
+        let mods_path: &String = match &loaded_arguments.mods_path {
+            Some(path) => path,
+            None => {
+                LogsInstance::warn(
+                    "Ignoring mods update because the --mods-path is not set",
+                    colored::Color::Yellow,
+                );
+                return Ok(());
+            }
+        };
+
+        let path: &Path = Path::new(mods_path);
+        if !path.is_dir() {
+            return Err(UpdaterError::InvalidPath(format!(
+                "--mods-path is not valid, {}",
+                path.display()
+            )));
+        }
+
+        // Mods only tracked by a modid.txt are moved to the state
+        let mut state: InstallState = InstallState::from_arguments(loaded_arguments)?;
+        state.save_or_warn();
+        let history: History = History::from_arguments(loaded_arguments)?;
+
+        let entries: fs::ReadDir = fs::read_dir(path).map_err(|err| {
+            UpdaterError::InvalidPath(format!("Error reading directory: {}", err))
+        })?;
+
+        let backup_path: PathBuf = get_backup_path(path, loaded_arguments);
+
+        let mut mod_paths: Vec<PathBuf> = Vec::new();
+        for entry_result in entries {
+            match entry_result {
+                // Hidden entries are tool data like the .backup fallback folder
+                Ok(entry) if entry.file_name().to_string_lossy().starts_with('.') => {}
+                Ok(entry) => mod_paths.push(entry.path()),
+                Err(err) => LogsInstance::error(
+                    format!("Error reading directory entry: {}", err).as_str(),
+                    colored::Color::BrightRed,
+                ),
+            }
+        }
+        mod_paths.sort();
+
+        let total: usize = mod_paths.len();
+        for (index, path) in mod_paths.into_iter().enumerate() {
+            if let Some(name) = path.file_name() {
+                LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
+                LogsInstance::print(
+                    format!(
+                        "Updating Mod ({} of {}): {}",
+                        index + 1,
+                        total,
+                        name.to_string_lossy()
+                    )
+                    .as_str(),
+                    colored::Color::BrightWhite,
+                );
+
+                let (str_id, actual_fileid): (String, i64) =
+                    match state.mod_by_folder(&name.to_string_lossy()) {
+                        Some((id, tracked)) => (id.clone(), tracked.fileid),
+                        None => {
+                            LogsInstance::print(
+                                format!(
+                                    "Not tracked, ignoring: {}, install it with mods add",
+                                    name.to_string_lossy()
+                                )
+                                .as_str(),
+                                colored::Color::BrightWhite,
+                            );
+                            LogsInstance::print(
+                                "-----------------------------",
+                                colored::Color::BrightWhite,
+                            );
+                            continue;
+                        }
+                    };
+
+                if loaded_arguments.side_filter != arguments::SideFilter::Off
+                    && skip_by_side(
+                        loaded_arguments,
+                        &name.to_string_lossy(),
+                        get_mod_side(&path, &str_id),
+                    )
+                {
+                    LogsInstance::print(
+                        "-----------------------------",
+                        colored::Color::BrightWhite,
+                    );
+                    continue;
+                }
+
+                let ping_url: String = format!("{}{}", crate::MODS_URL, str_id);
+
+                LogsInstance::print(
+                    format!("Pinging: {}", ping_url).as_str(),
+                    colored::Color::BrightWhite,
+                );
+
+                let (biggest_id, biggest_filename) = Utils::get_mod_last_id_name_by_ping_url(
+                    &ping_url,
+                    loaded_arguments.mod_channel(&str_id),
+                );
+
+                let id_download: i64;
+                let filename_download: String;
+                if let Some(id) = biggest_id {
+                    if actual_fileid >= id {
+                        LogsInstance::print(
+                            format!("Mod: {} is already on last version", ping_url).as_str(),
+                            colored::Color::Green,
+                        );
+                        LogsInstance::print(
+                            "-----------------------------",
+                            colored::Color::BrightWhite,
+                        );
+                        continue;
+                    }
+                    id_download = id;
+                } else {
+                    LogsInstance::error(
+                        format!("File id parse failed for: {}", mods_path).as_str(),
+                        colored::Color::BrightRed,
+                    );
+                    LogsInstance::print(
+                        "-----------------------------",
+                        colored::Color::BrightWhite,
+                    );
+                    continue;
+                }
+
+                if let Some(filename) = biggest_filename {
+                    filename_download = filename;
+                } else {
+                    LogsInstance::error(
+                        format!("File name parse failed for: {}", mods_path).as_str(),
+                        colored::Color::BrightRed,
+                    );
+                    LogsInstance::print(
+                        "-----------------------------",
+                        colored::Color::BrightWhite,
+                    );
+                    continue;
+                }
+
+                let url_download: String = format!(
+                    "{}download/{}/{}",
+                    crate::MODS_URL,
+                    id_download,
+                    filename_download
+                );
+
+                LogsInstance::print(
+                    format!("Mod update available: {}", url_download).as_str(),
+                    colored::Color::BrightGreen,
+                );
+
+                if !Utils::url_exists(&url_download) {
+                    LogsInstance::error(
+                        "No connection or the mod does no longer exist",
+                        colored::Color::BrightRed,
+                    );
+                    LogsInstance::print(
+                        "-----------------------------",
+                        colored::Color::BrightWhite,
+                    );
+                    continue;
+                }
+
+                if let Err(e) = server.stop() {
+                    hooks.failed_mods.push(str_id);
+                    return Err(UpdaterError::Server(format!(
+                        "Cannot stop the server, mods will not be updated: {}",
+                        e
+                    )));
+                }
+
+                if let Err(e) = world_backup.create_once() {
+                    hooks.failed_mods.push(str_id);
+                    return Err(UpdaterError::Backup(format!(
+                        "Cannot backup the world, mods will not be updated: {}",
+                        e
+                    )));
+                }
+
+                let started: Instant = Instant::now();
+                let mut entry: HistoryEntry = HistoryEntry::new(HistoryAction::ModUpdate, &str_id);
+                entry.from = installed_version(&path);
+                entry.url = Some(url_download.clone());
+
+                match backup_mod(&path, &backup_path, &str_id) {
+                    Ok(backup) => LogsInstance::print(
+                        format!("Mod data moved to backup: {}", backup.display()).as_str(),
+                        colored::Color::Green,
+                    ),
+                    // The mod is left as it is, it cannot be replaced without a way back
+                    Err(e) => {
+                        let error: String = format!("Failed to backup mod data: {}", e);
+                        LogsInstance::error(error.as_str(), colored::Color::BrightRed);
+                        history.record::<(), _>(entry, &Err(error), started);
+                        hooks.failed_mods.push(str_id);
+                        LogsInstance::print(
+                            "-----------------------------",
+                            colored::Color::BrightWhite,
+                        );
+                        continue;
+                    }
+                }
+
+                let result: Result<PathBuf, String> = install_mod_update(
+                    &path,
+                    name,
+                    &url_download,
+                    &filename_download,
+                    &str_id,
+                    id_download,
+                );
+                if let Ok(updated) = &result {
+                    entry.to = installed_version(updated);
+                }
+                history.record(entry, &result, started);
+
+                match result {
+                    Ok(updated) => {
+                        LogsInstance::print(
+                            "Successfully updated the mod",
+                            colored::Color::BrightGreen,
+                        );
+                        state.set_mod(&str_id, &updated, id_download);
+                        state.save_or_warn();
+                        hooks.changed_mods.push(str_id.clone());
+                    }
+                    Err(e) => {
+                        LogsInstance::error(
+                            format!("Failed to update the mod: {}", e).as_str(),
+                            colored::Color::BrightRed,
+                        );
+                        hooks.failed_mods.push(str_id.clone());
+
+                        match restore_mod(&path, &backup_path, &str_id) {
+                            Ok(restored) => LogsInstance::warn(
+                                format!("Previous version restored: {}", restored.display())
+                                    .as_str(),
+                                colored::Color::Yellow,
+                            ),
+                            Err(e) => LogsInstance::error(
+                                format!(
+                                    "Cannot restore the previous version: {}, check {}",
+                                    e,
+                                    backup_path.display()
+                                )
+                                .as_str(),
+                                colored::Color::BrightRed,
+                            ),
+                        }
+                    }
+                }
+
+                LogsInstance::print("-----------------------------", colored::Color::BrightWhite);
+            }
+        }
+
+        hooks.run(Hook::PostMods)
+    }
+
+    /// Creates a modid.txt folder for each mod of --generate-modpack, the next update installs
+    /// them
+    pub fn generate(&self) -> Result<(), UpdaterError> {
+        let loaded_arguments: &arguments::Items = self.arguments;
+
+        let mods_path = match &loaded_arguments.mods_path {
+            Some(path) => path,
+            None => {
+                LogsInstance::warn(
+                    "Ignoring mods update because the --mods-path is not set",
+                    colored::Color::Yellow,
+                );
+                return Ok(());
+            }
+        };
+
+        let path: &Path = Path::new(mods_path);
+        if !path.is_dir() {
+            return Err(UpdaterError::InvalidPath(format!(
+                "--mods-path is not valid, {}",
+                path.display()
+            )));
+        }
+
+        let id_mods: Vec<String> = loaded_arguments
+            .generate_modpack
+            .clone()
+            .unwrap_or_default();
+
+        // Generate mod id file
+        for mod_id in &id_mods {
+            if loaded_arguments.side_filter != arguments::SideFilter::Off {
+                let side: Option<String> = ModDb::get_mod_by_slug(mod_id)
+                    .ok()
+                    .and_then(|detail| detail.side);
+                if skip_by_side(loaded_arguments, mod_id, side) {
+                    continue;
+                }
+            }
+
+            let mod_folder: PathBuf = path.join(format!("{}_0.0.0", mod_id));
+            let modid_file: PathBuf = mod_folder.join("modid.txt");
+
+            // Create all necessary folders
+            if let Err(err) = fs::create_dir_all(&mod_folder) {
+                LogsInstance::error(
+                    format!("Cannot create folder '{}': {}", mod_folder.display(), err).as_str(),
+                    colored::Color::BrightRed,
+                );
+                continue;
+            }
+
+            // Creates the file
+            match fs::File::create(&modid_file) {
+                Ok(mut file) => {
+                    if let Err(err) = file.write_all(format!("{}\n{}", mod_id, 0).as_bytes()) {
+                        LogsInstance::error(
+                            format!("Cannot write to modid.txt: {}", err).as_str(),
+                            colored::Color::BrightRed,
+                        );
+                    }
+                }
+                Err(err) => {
+                    LogsInstance::error(
+                        format!("Cannot create modid.txt: {}", err).as_str(),
+                        colored::Color::BrightRed,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use crate::arguments;
+
+struct Rule {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+    /// Literal folder before the first wildcard of an anchored pattern, None when the
+    /// pattern can match at any depth
+    prefix: Option<String>,
+}
+
+/// Gitignore style patterns of the items preserved during the update, the last matching
+/// pattern wins so `!` can take back part of a previous one
+pub struct IgnorePatterns {
+    rules: Vec<Rule>,
+}
+
+impl IgnorePatterns {
+    pub fn from_arguments(loaded_arguments: &arguments::Items) -> Result<IgnorePatterns, String> {
+        let patterns: Vec<&str> = loaded_arguments
+            .ignore_folders
+            .iter()
+            .flatten()
+            .chain(loaded_arguments.ignore_files.iter().flatten())
+            .map(String::as_str)
+            .collect();
+
+        IgnorePatterns::new(&patterns)
+    }
+
+    pub fn new(patterns: &[&str]) -> Result<IgnorePatterns, String> {
+        let mut rules: Vec<Rule> = Vec::new();
+        for pattern in patterns {
+            if let Some(rule) = IgnorePatterns::parse(pattern)? {
+                rules.push(rule);
+            }
+        }
+
+        Ok(IgnorePatterns { rules })
+    }
+
+    fn parse(pattern: &str) -> Result<Option<Rule>, String> {
+        let mut pattern: &str = pattern.trim();
+
+        let negated: bool = pattern.starts_with('!');
+        if negated {
+            pattern = &pattern[1..];
+        }
+
+        let dir_only: bool = pattern.ends_with('/');
+        pattern = pattern.trim_end_matches('/');
+
+        // Like gitignore, a pattern without slash matches the name at any depth
+        let anchored: bool = pattern.contains('/');
+        pattern = pattern.trim_start_matches('/');
+
+        if pattern.is_empty() {
+            return Ok(None);
+        }
+
+        let prefix: Option<String> = anchored.then(|| {
+            match pattern.find(['*', '?']) {
+                Some(index) => pattern[..index].rsplit_once('/').map_or("", |(dir, _)| dir),
+                None => pattern,
+            }
+            .to_string()
+        });
+
+        // folder/** also matches the folder itself, not only its contents
+        let whole_dir: Option<&str> = pattern.strip_suffix("/**");
+        if let Some(dir) = whole_dir {
+            pattern = dir;
+        }
+
+        let mut regex: String = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut index: usize = 0;
+
+        while index < chars.len() {
+            match chars[index] {
+                '*' if chars.get(index + 1) == Some(&'*') => {
+                    if chars.get(index + 2) == Some(&'/') {
+                        regex.push_str("(?:.*/)?");
+                        index += 3;
+                    } else {
+                        regex.push_str(".*");
+                        index += 2;
+                    }
+                    continue;
+                }
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                c => regex.push_str(&regex::escape(&c.to_string())),
+            }
+            index += 1;
+        }
+        if whole_dir.is_some() {
+            regex.push_str("(?:/.*)?");
+        }
+        regex.push('$');
+
+        let regex: Regex =
+            Regex::new(&regex).map_err(|e| format!("Invalid ignore pattern {}: {}", pattern, e))?;
+
+        Ok(Some(Rule {
+            regex,
+            negated,
+            dir_only,
+            prefix,
+        }))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// True when the path, relative to the working path with / separators, is preserved,
+    /// a path inside a preserved folder is preserved too
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let ancestors: Vec<&str> = path
+            .match_indices('/')
+            .map(|(index, _)| &path[..index])
+            .collect();
+
+        let mut preserved: bool = false;
+
+        for rule in &self.rules {
+            let matched: bool = ((is_dir || !rule.dir_only) && rule.regex.is_match(path))
+                || ancestors
+                    .iter()
+                    .any(|ancestor| rule.regex.is_match(ancestor));
+
+            if matched {
+                preserved = !rule.negated;
+            }
+        }
+
+        preserved
+    }
+
+    /// True when a negated pattern can match the folder or something inside it
+    fn negated_inside(&self, dir: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| rule.negated)
+            .any(|rule| match &rule.prefix {
+                Some(prefix) if !prefix.is_empty() => {
+                    prefix == dir
+                        || prefix.starts_with(&format!("{}/", dir))
+                        || dir.starts_with(&format!("{}/", prefix))
+                }
+                _ => true,
+            })
+    }
+
+    /// Items of the working path to preserve, relative with / separators, a folder is
+    /// returned whole when no negated pattern can take back part of it
+    pub fn collect(&self, working_path: &Path, skip: &dyn Fn(&Path) -> bool) -> Vec<String> {
+        let mut items: Vec<String> = Vec::new();
+        if self.is_empty() {
+            return items;
+        }
+
+        let mut pending: Vec<PathBuf> = vec![working_path.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries: std::iter::Peekable<fs::ReadDir> = match fs::read_dir(&dir) {
+                Ok(entries) => entries.peekable(),
+                Err(_) => continue,
+            };
+
+            // A preserved folder walked for a negation is kept even when empty
+            if entries.peek().is_none()
+                && let Ok(relative) = dir.strip_prefix(working_path)
+            {
+                let relative: String = relative.to_string_lossy().replace('\\', "/");
+                if !relative.is_empty() && self.matches(&relative, true) {
+                    items.push(relative);
+                }
+            }
+
+            for entry in entries.flatten() {
+                let path: PathBuf = entry.path();
+                if skip(&path) {
+                    continue;
+                }
+
+                let relative: String = match path.strip_prefix(working_path) {
+                    Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+                    Err(_) => continue,
+                };
+
+                let is_dir: bool = entry.file_type().is_ok_and(|kind| kind.is_dir());
+
+                if is_dir {
+                    if self.matches(&relative, true) && !self.negated_inside(&relative) {
+                        items.push(relative);
+                    } else {
+                        pending.push(path);
+                    }
+                } else if self.matches(&relative, false) {
+                    items.push(relative);
+                }
+            }
+        }
+
+        items.sort();
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(list: &[&str]) -> IgnorePatterns {
+        IgnorePatterns::new(list).unwrap()
+    }
+
+    /// Empty folder in the temp folder, removed before the test uses it
+    fn test_dir(name: &str) -> PathBuf {
+        let dir: PathBuf = std::env::temp_dir().join(format!(
+            "vs_updater_patterns_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn star_stays_in_one_folder() {
+        let ignore: IgnorePatterns = patterns(&["assets/*.json"]);
+        assert!(ignore.matches("assets/config.json", false));
+        assert!(!ignore.matches("assets/game/config.json", false));
+        assert!(!ignore.matches("config.json", false));
+    }
+
+    #[test]
+    fn double_star_crosses_folders() {
+        let ignore: IgnorePatterns = patterns(&["assets/**/custom.json"]);
+        assert!(ignore.matches("assets/custom.json", false));
+        assert!(ignore.matches("assets/game/lang/custom.json", false));
+        assert!(!ignore.matches("Mods/custom.json", false));
+    }
+
+    #[test]
+    fn question_mark_is_one_character() {
+        let ignore: IgnorePatterns = patterns(&["run?.sh"]);
+        assert!(ignore.matches("run1.sh", false));
+        assert!(!ignore.matches("run.sh", false));
+        assert!(!ignore.matches("run12.sh", false));
+        assert!(!ignore.matches("run/.sh", false));
+    }
+
+    #[test]
+    fn name_without_slash_matches_at_any_depth() {
+        let ignore: IgnorePatterns = patterns(&["*.sh"]);
+        assert!(ignore.matches("start.sh", false));
+        assert!(ignore.matches("scripts/tools/start.sh", false));
+    }
+
+    #[test]
+    fn negation_takes_back_part_of_a_pattern() {
+        let ignore: IgnorePatterns = patterns(&["*.sh", "!update.sh"]);
+        assert!(ignore.matches("start.sh", false));
+        assert!(!ignore.matches("update.sh", false));
+
+        let ignore: IgnorePatterns = patterns(&["ServerData", "!ServerData/Logs/"]);
+        assert!(ignore.matches("ServerData/serverconfig.json", false));
+        assert!(!ignore.matches("ServerData/Logs", true));
+        assert!(!ignore.matches("ServerData/Logs/server-main.log", false));
+    }
+
+    #[test]
+    fn trailing_slash_only_matches_folders() {
+        let ignore: IgnorePatterns = patterns(&["custom/"]);
+        assert!(ignore.matches("custom", true));
+        assert!(!ignore.matches("custom", false));
+        assert!(ignore.matches("custom/file.txt", false));
+    }
+
+    #[test]
+    fn leading_slash_is_anchored() {
+        let ignore: IgnorePatterns = patterns(&["/run.sh"]);
+        assert!(ignore.matches("run.sh", false));
+        assert!(!ignore.matches("scripts/run.sh", false));
+    }
+
+    #[test]
+    fn double_star_suffix_matches_the_folder() {
+        let ignore: IgnorePatterns = patterns(&["ServerData/**"]);
+        assert!(ignore.matches("ServerData", true));
+        assert!(ignore.matches("ServerData/Saves/default.vcdbs", false));
+        assert!(!ignore.matches("ServerDataOld", true));
+    }
+
+    #[test]
+    fn collect_keeps_folders_whole_outside_negations() {
+        let dir: PathBuf = test_dir("whole");
+        fs::create_dir_all(dir.join("ServerData/Logs")).unwrap();
+        fs::create_dir_all(dir.join("ServerData/Saves")).unwrap();
+        fs::create_dir_all(dir.join("Custom/Empty")).unwrap();
+        fs::write(dir.join("ServerData/Logs/server-main.log"), "").unwrap();
+        fs::write(dir.join("ServerData/serverconfig.json"), "").unwrap();
+
+        let ignore: IgnorePatterns = patterns(&["ServerData/**", "Custom/", "!ServerData/Logs/"]);
+        let items: Vec<String> = ignore.collect(&dir, &|_: &Path| false);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(
+            items,
+            vec!["Custom", "ServerData/Saves", "ServerData/serverconfig.json"]
+        );
+    }
+
+    #[test]
+    fn collect_keeps_empty_folders_walked_for_a_negation() {
+        let dir: PathBuf = test_dir("empty");
+        fs::create_dir_all(dir.join("Backups")).unwrap();
+        fs::write(dir.join("start.sh"), "").unwrap();
+
+        let ignore: IgnorePatterns = patterns(&["Backups/", "*.sh", "!*.log"]);
+        let items: Vec<String> = ignore.collect(&dir, &|_: &Path| false);
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(items, vec!["Backups", "start.sh"]);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    io::{IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use crate::logger::{LogLevel, LogsInstance};
+
+/// Time between two progress lines when the output is not a terminal
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+/// Time between two redraws of the progress bar
+const DRAW_INTERVAL: Duration = Duration::from_millis(100);
+const BAR_WIDTH: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgressUnit {
+    Bytes,
+    Files,
+}
+
+/// Progress of a download or an extraction, drawn as a bar on a terminal and written as
+/// periodic log lines otherwise, nothing is shown with -q
+pub struct Progress {
+    label: String,
+    unit: ProgressUnit,
+    total: Option<u64>,
+    current: u64,
+    started: Instant,
+    last_shown: Option<Instant>,
+    interactive: bool,
+    enabled: bool,
+}
+
+impl Progress {
+    pub fn bytes(label: &str, total: Option<u64>) -> Progress {
+        Progress::new(label, ProgressUnit::Bytes, total)
+    }
+
+    pub fn files(label: &str, total: Option<u64>) -> Progress {
+        Progress::new(label, ProgressUnit::Files, total)
+    }
+
+    fn new(label: &str, unit: ProgressUnit, total: Option<u64>) -> Progress {
+        Progress {
+            label: label.to_string(),
+            unit,
+            total: total.filter(|total| *total > 0),
+            current: 0,
+            started: Instant::now(),
+            last_shown: None,
+            interactive: std::io::stdout().is_terminal(),
+            enabled: LogsInstance::level() >= LogLevel::Info,
+        }
+    }
+
+    pub fn update(&mut self, current: u64) {
+        self.current = current;
+        if !self.enabled {
+            return;
+        }
+
+        // The bar is drawn at once, the first log line only comes after an interval so short
+        // steps only leave their summary
+        let (interval, shown): (Duration, Option<Instant>) = if self.interactive {
+            (DRAW_INTERVAL, self.last_shown)
+        } else {
+            (LOG_INTERVAL, Some(self.last_shown.unwrap_or(self.started)))
+        };
+        if shown.is_some_and(|shown| shown.elapsed() < interval) {
+            return;
+        }
+        self.last_shown = Some(Instant::now());
+
+        if self.interactive {
+            print!("\r{}\x1b[K", self.line(true));
+            let _ = std::io::stdout().flush();
+        } else {
+            LogsInstance::print(self.line(false).as_str(), colored::Color::White);
+        }
+    }
+
+    /// Replaces the bar with a summary of the size, time and rate
+    pub fn finish(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.interactive && self.last_shown.is_some() {
+            print!("\r\x1b[K");
+            let _ = std::io::stdout().flush();
+        }
+
+        let elapsed: Duration = self.started.elapsed();
+        let summary: String = match self.unit {
+            ProgressUnit::Bytes => format!(
+                "{}: {} in {} ({}/s)",
+                self.label,
+                Progress::format_bytes(self.current),
+                Progress::format_duration(elapsed),
+                Progress::format_bytes(self.rate() as u64)
+            ),
+            ProgressUnit::Files => format!(
+                "{}: {} files in {}",
+                self.label,
+                self.current,
+                Progress::format_duration(elapsed)
+            ),
+        };
+        LogsInstance::print(summary.as_str(), colored::Color::White);
+    }
+
+    /// Units per second since the start
+    fn rate(&self) -> f64 {
+        let seconds: f64 = self.started.elapsed().as_secs_f64();
+        if seconds > 0.0 {
+            self.current as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    fn line(&self, bar: bool) -> String {
+        let amount: String = match (self.unit, self.total) {
+            (ProgressUnit::Bytes, Some(total)) => format!(
+                "{} / {}",
+                Progress::format_bytes(self.current),
+                Progress::format_bytes(total)
+            ),
+            (ProgressUnit::Bytes, None) => Progress::format_bytes(self.current),
+            (ProgressUnit::Files, Some(total)) => format!("{} / {} files", self.current, total),
+            (ProgressUnit::Files, None) => format!("{} files", self.current),
+        };
+
+        let total: u64 = match self.total {
+            Some(total) => total,
+            None => return format!("{}: {}", self.label, amount),
+        };
+
+        let fraction: f64 = (self.current as f64 / total as f64).min(1.0);
+        let rate: f64 = self.rate();
+        let mut line: String = format!("{}: ", self.label);
+
+        if bar {
+            let filled: usize = (fraction * BAR_WIDTH as f64) as usize;
+            line.push_str(&format!(
+                "[{}{}] ",
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled)
+            ));
+        }
+        line.push_str(&format!("{:>3}% {}", (fraction * 100.0) as u64, amount));
+
+        if self.unit == ProgressUnit::Bytes {
+            line.push_str(&format!(", {}/s", Progress::format_bytes(rate as u64)));
+        }
+        if rate > 0.0 && self.current < total {
+            let remaining: f64 = (total - self.current) as f64 / rate;
+            line.push_str(&format!(
+                ", ETA {}",
+                Progress::format_duration(Duration::from_secs_f64(remaining))
+            ));
+        }
+
+        line
+    }
+
+    fn format_bytes(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+        let mut value: f64 = bytes as f64;
+        let mut unit: usize = 0;
+
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if unit == 0 {
+            format!("{} {}", bytes, UNITS[0])
+        } else {
+            format!("{:.1} {}", value, UNITS[unit])
+        }
+    }
+
+    fn format_duration(duration: Duration) -> String {
+        let seconds: u64 = duration.as_secs();
+        if seconds >= 60 {
+            format!("{}m{:02}s", seconds / 60, seconds % 60)
+        } else {
+            format!("{}s", seconds)
+        }
+    }
+}
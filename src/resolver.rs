@@ -0,0 +1,204 @@
+use crate::BASE_URL;
+use crate::arguments::{self, Channel};
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+use crate::version::GameVersion;
+
+/// Finds the versions published for the game type and architecture of the arguments, the
+/// channel and the version constraints of the arguments are applied
+pub struct VersionResolver<'a> {
+    arguments: &'a arguments::Items,
+    game_type: String,
+    arch: String,
+}
+
+impl<'a> VersionResolver<'a> {
+    pub fn new(
+        loaded_arguments: &'a arguments::Items,
+    ) -> Result<VersionResolver<'a>, UpdaterError> {
+        let game_type: String =
+            Utils::get_game_type(loaded_arguments.game_type.as_deref().unwrap_or("server"))
+                .map_err(UpdaterError::Unsupported)?;
+
+        let detected_arch: &str = if std::env::consts::ARCH == "aarch64" {
+            "arm64"
+        } else {
+            "x64"
+        };
+
+        Ok(VersionResolver {
+            arguments: loaded_arguments,
+            game_type,
+            arch: loaded_arguments
+                .arch
+                .clone()
+                .unwrap_or_else(|| detected_arch.to_string()),
+        })
+    }
+
+    /// Prefix of the archive names, like vs_server_linux-x64_
+    pub fn game_type(&self) -> &str {
+        &self.game_type
+    }
+
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    pub fn version_url(&self, version: &GameVersion) -> String {
+        let folder: &str = if version.is_pre() || version.is_rc() {
+            "unstable"
+        } else {
+            "stable"
+        };
+
+        format!(
+            "{}{}/{}{}{}",
+            BASE_URL,
+            folder,
+            self.game_type,
+            version,
+            Utils::get_compress_type()
+        )
+    }
+
+    /// Url of the version, checked to exist together with its ARM64 release when needed
+    pub fn available_url(&self, version: &GameVersion) -> Result<String, UpdaterError> {
+        let url: String = self.version_url(version);
+        LogsInstance::print(format!("Pinging: {}", url).as_str(), colored::Color::White);
+        if !Utils::url_exists(&url) {
+            return Err(UpdaterError::Version(format!(
+                "Version {} not found: {}",
+                version, url
+            )));
+        }
+
+        if self.arch == "arm64" && !Utils::url_exists(&Utils::arm64_url(&version.to_string())) {
+            return Err(UpdaterError::Version(format!(
+                "ARM64 release for {} not found",
+                version
+            )));
+        }
+
+        Ok(url)
+    }
+
+    /// Highest version of the game api lists for the channel, used for new installs
+    pub fn latest_published(&self) -> Result<GameVersion, UpdaterError> {
+        let mut versions: Vec<GameVersion> =
+            Utils::get_published_versions(false).map_err(UpdaterError::Network)?;
+        if self.arguments.game_channel() != Channel::Stable {
+            versions.extend(Utils::get_published_versions(true).map_err(UpdaterError::Network)?);
+        }
+
+        versions
+            .into_iter()
+            .filter(|version| self.arguments.accepts_game_version(version))
+            .max()
+            .ok_or_else(|| UpdaterError::Version(String::from("No available versions found")))
+    }
+
+    /// Latest version reachable from the installed one, found by pinging the next patches,
+    /// minors and majors and then the unstables of the channel, it can be the installed one
+    pub fn find_latest(&self, installed: &GameVersion) -> Result<GameVersion, UpdaterError> {
+        let mut game_version: GameVersion = installed.clone();
+        game_version.remove_pre_and_rc();
+        let mut last_version: GameVersion = GameVersion::default();
+
+        loop {
+            if self.is_available(&game_version) {
+                LogsInstance::print(
+                    format!("Version available: {}", game_version).as_str(),
+                    colored::Color::Green,
+                );
+                last_version = game_version.clone();
+                game_version.increment_patch();
+            } else {
+                if game_version.minor() != installed.minor() {
+                    if game_version.major() != installed.major() {
+                        LogsInstance::print(
+                            format!(
+                                "Latest version available: {}, installed version: {}",
+                                last_version, installed
+                            )
+                            .as_str(),
+                            colored::Color::BrightGreen,
+                        );
+                        break;
+                    } else {
+                        game_version.increment_major();
+                    }
+                } else {
+                    game_version.increment_minor();
+                }
+            }
+        }
+
+        if self.arguments.game_channel() != Channel::Stable {
+            // No release available to update
+            if last_version.empty() {
+                // Checking for unstable on actual game version
+                if installed.is_pre() || installed.is_rc() {
+                    game_version = installed.clone();
+                }
+                // Actual version is not unstable
+                else {
+                    game_version = installed.clone();
+                    game_version.increment_minor();
+                    game_version.increment_pre();
+                }
+            }
+            // Final release, increase minor for checking unstables
+            else {
+                game_version = last_version.clone();
+                game_version.increment_minor();
+                game_version.increment_pre();
+            }
+
+            loop {
+                if self.is_available(&game_version) {
+                    LogsInstance::print(
+                        format!("Unstable Version available: {}", game_version).as_str(),
+                        colored::Color::BrightGreen,
+                    );
+                    last_version = game_version.clone();
+
+                    if game_version.is_rc() {
+                        game_version.increment_rc();
+                    } else {
+                        game_version.increment_pre();
+                    }
+                } else {
+                    if game_version.is_pre() {
+                        game_version.increment_rc();
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if last_version.empty() {
+            return Err(UpdaterError::Version(String::from(
+                "No available versions found",
+            )));
+        }
+
+        Ok(last_version)
+    }
+
+    /// Versions outside the channel or the version constraints are handled as missing
+    fn is_available(&self, version: &GameVersion) -> bool {
+        if !self.arguments.accepts_game_version(version) {
+            return false;
+        }
+
+        let ping_url: String = self.version_url(version);
+        LogsInstance::print(
+            format!("Pinging: {}", ping_url).as_str(),
+            colored::Color::White,
+        );
+        Utils::url_exists(&ping_url)
+    }
+}
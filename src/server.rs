@@ -0,0 +1,314 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::arguments;
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+
+pub struct ServerLifecycle {
+    working_path: PathBuf,
+    enabled: bool,
+    stop_command: Option<String>,
+    start_command: Option<String>,
+    console: Option<String>,
+    stop_timeout: u64,
+    /// Seconds before the stop where players are warned, biggest first
+    warnings: Vec<u64>,
+    announce_command: Option<String>,
+    restart_message: String,
+    stopped: bool,
+    install_modified: bool,
+}
+
+impl ServerLifecycle {
+    pub fn new(loaded_arguments: &arguments::Items, working_path: &Path) -> ServerLifecycle {
+        ServerLifecycle {
+            working_path: working_path
+                .canonicalize()
+                .unwrap_or_else(|_| working_path.to_path_buf()),
+            enabled: loaded_arguments.stop_server,
+            stop_command: loaded_arguments.stop_command.clone(),
+            start_command: loaded_arguments.start_command.clone(),
+            console: loaded_arguments.server_console.clone(),
+            stop_timeout: loaded_arguments.stop_timeout,
+            warnings: ServerLifecycle::parse_warnings(&loaded_arguments.restart_warnings),
+            announce_command: loaded_arguments.announce_command.clone(),
+            restart_message: loaded_arguments.restart_message.clone(),
+            stopped: false,
+            install_modified: false,
+        }
+    }
+
+    fn parse_warnings(warnings: &Option<Vec<String>>) -> Vec<u64> {
+        let mut parsed: Vec<u64> = Vec::new();
+
+        for warning in warnings.iter().flatten() {
+            match Utils::parse_duration(warning) {
+                Some(seconds) if seconds > 0 => parsed.push(seconds),
+                _ => LogsInstance::warn(
+                    format!("Invalid restart warning ignored: {}", warning).as_str(),
+                    colored::Color::Yellow,
+                ),
+            }
+        }
+
+        parsed.sort_unstable_by(|a, b| b.cmp(a));
+        parsed.dedup();
+        parsed
+    }
+
+    /// Pid of a VintagestoryServer process running from the working path
+    pub fn find_server_pid(&self) -> Option<u32> {
+        if cfg!(target_os = "windows") {
+            self.find_server_pid_windows()
+        } else {
+            self.find_server_pid_linux()
+        }
+    }
+
+    fn find_server_pid_linux(&self) -> Option<u32> {
+        for entry in fs::read_dir("/proc").ok()?.flatten() {
+            let pid: u32 = match entry.file_name().to_string_lossy().parse() {
+                Ok(pid) => pid,
+                Err(_) => continue,
+            };
+
+            let cmdline: Vec<u8> = match fs::read(entry.path().join("cmdline")) {
+                Ok(cmdline) => cmdline,
+                Err(_) => continue,
+            };
+
+            let args: Vec<String> = cmdline
+                .split(|byte| *byte == 0)
+                .map(|arg| String::from_utf8_lossy(arg).to_string())
+                .collect();
+
+            // The server runs as ./VintagestoryServer or dotnet VintagestoryServer.dll
+            let server_arg: Option<&String> = args.iter().take(2).find(|arg| {
+                let name: &str = arg.rsplit(['/', '\\']).next().unwrap_or("");
+                name == "VintagestoryServer" || name == "VintagestoryServer.dll"
+            });
+
+            let server_arg: &String = match server_arg {
+                Some(arg) => arg,
+                None => continue,
+            };
+
+            let cwd: PathBuf = fs::read_link(entry.path().join("cwd")).unwrap_or_default();
+            let server_path: PathBuf = cwd.join(server_arg);
+            let server_path: PathBuf = server_path.canonicalize().unwrap_or(server_path);
+
+            if server_path.starts_with(&self.working_path) || cwd == self.working_path {
+                return Some(pid);
+            }
+        }
+
+        None
+    }
+
+    fn find_server_pid_windows(&self) -> Option<u32> {
+        let cmd: String = format!(
+            "Get-Process -Name VintagestoryServer -ErrorAction SilentlyContinue | Where-Object {{ $_.Path -like '{}*' }} | ForEach-Object {{ $_.Id }}",
+            self.working_path.display()
+        );
+
+        let output = Command::new("powershell")
+            .args(["-Command", &cmd])
+            .output()
+            .ok()?;
+
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.trim().parse().ok())
+    }
+
+    /// Writes a command to the server console, accepts `tmux:<session>`, `screen:<session>`
+    /// or the path of a named pipe read by the server
+    pub fn send_console(&self, command: &str) -> Result<(), String> {
+        let console: &str = self
+            .console
+            .as_deref()
+            .ok_or("--server-console is not set")?;
+
+        LogsInstance::debug(format!("Sending to {}: {}", console, command).as_str());
+
+        let status = if let Some(session) = console.strip_prefix("tmux:") {
+            Command::new("tmux")
+                .args(["send-keys", "-t", session, command, "Enter"])
+                .status()
+        } else if let Some(session) = console.strip_prefix("screen:") {
+            Command::new("screen")
+                .args(["-S", session, "-p", "0", "-X", "stuff"])
+                .arg(format!("{}\n", command))
+                .status()
+        } else {
+            let mut pipe: fs::File = fs::OpenOptions::new()
+                .write(true)
+                .open(console)
+                .map_err(|e| format!("Cannot open server console {}: {}", console, e))?;
+            return pipe
+                .write_all(format!("{}\n", command).as_bytes())
+                .map_err(|e| format!("Cannot write to server console {}: {}", console, e));
+        };
+
+        match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("Console command failed with status: {}", status)),
+            Err(e) => Err(format!("Cannot send to server console: {}", e)),
+        }
+    }
+
+    /// Sends a message to the players with --announce-command or /announce in the console
+    pub fn announce(&self, message: &str) -> Result<(), String> {
+        if let Some(command) = &self.announce_command {
+            return Utils::run_shell_command(
+                &command.replace("{message}", message),
+                &[("VS_ANNOUNCE_MESSAGE", message.to_string())],
+            );
+        }
+
+        if self.console.is_some() {
+            return self.send_console(&format!("/announce {}", message));
+        }
+
+        Err(String::from(
+            "No --announce-command or --server-console set, players cannot be warned",
+        ))
+    }
+
+    /// True when the players will be warned with --restart-warnings before the stop, the
+    /// local countdown is not needed in that case
+    pub fn announces_restart(&self) -> bool {
+        self.enabled
+            && !self.stopped
+            && !self.warnings.is_empty()
+            && self.find_server_pid().is_some()
+    }
+
+    fn warn_players(&self) {
+        for (index, seconds) in self.warnings.iter().enumerate() {
+            let message: String = self
+                .restart_message
+                .replace("{time}", &Utils::format_duration(*seconds));
+
+            LogsInstance::print(
+                format!("Announcing: {}", message).as_str(),
+                colored::Color::BrightYellow,
+            );
+
+            if let Err(e) = self.announce(&message) {
+                LogsInstance::warn(e.as_str(), colored::Color::Yellow);
+            }
+
+            let next: u64 = self.warnings.get(index + 1).copied().unwrap_or(0);
+            thread::sleep(Duration::from_secs(seconds - next));
+        }
+    }
+
+    /// Stops the server if it is running from the working path, does nothing if
+    /// --stop-server is not set or the server is already stopped
+    pub fn stop(&mut self) -> Result<(), UpdaterError> {
+        if self.stopped {
+            return Ok(());
+        }
+
+        let pid: u32 = match self.find_server_pid() {
+            Some(pid) => pid,
+            None => return Ok(()),
+        };
+
+        if !self.enabled {
+            LogsInstance::warn(
+                format!(
+                    "VintagestoryServer is running from the working path (pid {}), use --stop-server to stop it before updating",
+                    pid
+                )
+                .as_str(),
+                colored::Color::BrightYellow,
+            );
+            return Ok(());
+        }
+
+        self.warn_players();
+
+        LogsInstance::print(
+            format!("Stopping VintagestoryServer (pid {})...", pid).as_str(),
+            colored::Color::BrightYellow,
+        );
+
+        if let Some(stop_command) = &self.stop_command {
+            Utils::run_shell_command(stop_command, &[]).map_err(UpdaterError::Server)?;
+        } else if self.console.is_some() {
+            self.send_console("/stop").map_err(UpdaterError::Server)?;
+        } else {
+            Utils::terminate_process(pid).map_err(UpdaterError::Server)?;
+        }
+
+        let started: Instant = Instant::now();
+        while Utils::process_alive(pid) {
+            if started.elapsed() > Duration::from_secs(self.stop_timeout) {
+                return Err(UpdaterError::Server(format!(
+                    "VintagestoryServer (pid {}) did not stop after {} seconds",
+                    pid, self.stop_timeout
+                )));
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+
+        self.stopped = true;
+        LogsInstance::print("VintagestoryServer stopped", colored::Color::Green);
+
+        Ok(())
+    }
+
+    /// Starts the server again if it was stopped by the tool
+    pub fn restart(&mut self) -> Result<(), UpdaterError> {
+        if !self.stopped {
+            return Ok(());
+        }
+
+        let start_command: &String = match &self.start_command {
+            Some(command) => command,
+            None => {
+                LogsInstance::warn(
+                    "No --start-command set, the server must be started manually",
+                    colored::Color::BrightYellow,
+                );
+                return Ok(());
+            }
+        };
+
+        LogsInstance::print("Starting VintagestoryServer...", colored::Color::White);
+        Utils::run_shell_command(start_command, &[]).map_err(UpdaterError::Server)?;
+        self.stopped = false;
+        LogsInstance::print("VintagestoryServer started", colored::Color::BrightGreen);
+
+        Ok(())
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Marks that the game files were changed, a failure after this leaves an install the
+    /// server cannot start from
+    pub fn mark_install_modified(&mut self) {
+        self.install_modified = true;
+    }
+
+    /// The new install is complete, the server can start from it again
+    pub fn mark_install_complete(&mut self) {
+        self.install_modified = false;
+    }
+
+    pub fn install_modified(&self) -> bool {
+        self.install_modified
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arguments::{self, Channel};
+use crate::error::UpdaterError;
+use crate::logger::LogsInstance;
+use crate::utils::Utils;
+use crate::version::GameVersion;
+
+/// State of the install kept between runs in the working path, skipped by the clean
+pub const STATE_FILE: &str = ".vs_updater_state.json";
+
+/// Game version installed by the tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameState {
+    pub version: GameVersion,
+    pub channel: Channel,
+    pub arch: String,
+    pub url: String,
+    /// SHA-256 of the downloaded archive, None when it could not be computed
+    pub hash: Option<String>,
+    pub arm64_patched: bool,
+    /// Unix seconds
+    pub installed_at: u64,
+}
+
+/// Mod installed from the mod database, keyed by the mod page id in the state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModState {
+    /// Folder name inside the mods path
+    pub folder: String,
+    pub fileid: i64,
+    /// Unix seconds
+    pub updated_at: u64,
+}
+
+/// Installed game and mods, read and updated by every command so nothing has to be guessed
+/// from file names, mods only tracked by a modid.txt are migrated when loaded
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct InstallState {
+    #[serde(skip)]
+    working_path: PathBuf,
+    #[serde(default)]
+    pub game: Option<GameState>,
+    #[serde(default)]
+    pub mods: BTreeMap<String, ModState>,
+}
+
+impl InstallState {
+    /// A missing or invalid state starts empty, everything in it can be found again on disk
+    pub fn load(working_path: &Path) -> InstallState {
+        let path: PathBuf = working_path.join(STATE_FILE);
+
+        let mut state: InstallState = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                LogsInstance::warn(
+                    format!(
+                        "Invalid state file {}, starting a new one: {}",
+                        path.display(),
+                        e
+                    )
+                    .as_str(),
+                    colored::Color::Yellow,
+                );
+                InstallState::default()
+            }),
+            Err(_) => InstallState::default(),
+        };
+        state.working_path = working_path.to_path_buf();
+
+        state
+    }
+
+    /// State of the working path from the arguments, with the mods of --mods-path migrated
+    pub fn from_arguments(
+        loaded_arguments: &arguments::Items,
+    ) -> Result<InstallState, UpdaterError> {
+        let working_path: PathBuf = Utils::working_path(&loaded_arguments.working_path)?;
+
+        let mut state: InstallState = InstallState::load(&working_path);
+        if let Some(mods_path) = &loaded_arguments.mods_path {
+            state.migrate_mods(Path::new(mods_path));
+        }
+
+        Ok(state)
+    }
+
+    /// Written to a temporary file and renamed, so a crash never leaves half a state
+    pub fn save(&self) -> Result<(), String> {
+        let path: PathBuf = self.working_path.join(STATE_FILE);
+        let partial: PathBuf = self.working_path.join(format!("{}.partial", STATE_FILE));
+
+        let contents: String = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Cannot serialize the state: {}", e))?;
+
+        fs::write(&partial, contents)
+            .and_then(|_| fs::rename(&partial, &path))
+            .map_err(|e| format!("Cannot write the state {}: {}", path.display(), e))
+    }
+
+    /// Saves and only warns on failure, the files on disk are already changed
+    pub fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            LogsInstance::warn(e.as_str(), colored::Color::Yellow);
+        }
+    }
+
+    /// Forgets the mods whose folder is gone and adds the folders that only have a modid.txt
+    pub fn migrate_mods(&mut self, mods_path: &Path) {
+        self.mods
+            .retain(|_, tracked| mods_path.join(&tracked.folder).exists());
+
+        let entries: fs::ReadDir = match fs::read_dir(mods_path) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let folder: String = entry.file_name().to_string_lossy().to_string();
+            if folder.starts_with('.') || self.mod_by_folder(&folder).is_some() {
+                continue;
+            }
+
+            if let (Some(id), fileid) = InstallState::read_modid_file(&entry.path())
+                && !self.mods.contains_key(&id)
+            {
+                self.mods.insert(
+                    id,
+                    ModState {
+                        folder,
+                        fileid: fileid.and_then(|fileid| fileid.parse().ok()).unwrap_or(0),
+                        updated_at: InstallState::now(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Mod page id and file id from the modid.txt of a mod folder
+    pub fn read_modid_file(mod_path: &Path) -> (Option<String>, Option<String>) {
+        match fs::read_to_string(mod_path.join("modid.txt")) {
+            Ok(contents) => {
+                let mut lines = contents
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty());
+                (lines.next(), lines.next())
+            }
+            Err(_) => (None, None),
+        }
+    }
+
+    pub fn mod_by_folder(&self, folder: &str) -> Option<(&String, &ModState)> {
+        self.mods
+            .iter()
+            .find(|(_, tracked)| tracked.folder == folder)
+    }
+
+    pub fn set_mod(&mut self, id: &str, mod_path: &Path, fileid: i64) {
+        let folder: String = mod_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        self.mods.insert(
+            id.to_string(),
+            ModState {
+                folder,
+                fileid,
+                updated_at: InstallState::now(),
+            },
+        );
+    }
+
+    pub fn remove_mod(&mut self, folder: &str) {
+        self.mods.retain(|_, tracked| tracked.folder != folder);
+    }
+
+    pub fn set_game(&mut self, game: GameState) {
+        self.game = Some(game);
+    }
+
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
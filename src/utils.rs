@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::env;
 use std::ffi::OsStr;
 use std::fs;
@@ -7,13 +8,49 @@ use std::io::BufReader;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use regex::Regex;
 
-use crate::logger::LogsInstance;
+use crate::arguments::{Channel, LeftoverTemp};
+use crate::error::UpdaterError;
+use crate::history;
+use crate::journal;
+use crate::lock;
+use crate::logger::{self, LogsInstance};
+use crate::progress::Progress;
+use crate::state;
+use crate::version::GameVersion;
+
+/// Files of the installed version, only these are deleted by the next update
+pub const MANIFEST_FILE: &str = ".vs_updater_manifest.txt";
+
+/// System folders that can never be a working path
+const PROTECTED_PATHS: [&str; 21] = [
+    "/",
+    "/bin",
+    "/boot",
+    "/dev",
+    "/etc",
+    "/home",
+    "/lib",
+    "/opt",
+    "/proc",
+    "/root",
+    "/run",
+    "/srv",
+    "/sys",
+    "/tmp",
+    "/usr",
+    "/var",
+    "C:\\",
+    "C:\\Windows",
+    "C:\\Users",
+    "C:\\Program Files",
+    "C:\\Program Files (x86)",
+];
 
 pub struct Utils;
 
@@ -37,20 +74,84 @@ impl Utils {
         false
     }
 
+    pub fn run_shell_command(command: &str, envs: &[(&str, String)]) -> Result<(), String> {
+        let mut shell: Command = if cfg!(target_os = "windows") {
+            let mut shell: Command = Command::new("cmd");
+            shell.args(["/C", command]);
+            shell
+        } else {
+            let mut shell: Command = Command::new("sh");
+            shell.args(["-c", command]);
+            shell
+        };
+
+        let status = shell
+            .envs(envs.iter().map(|(key, value)| (*key, value.as_str())))
+            .status()
+            .map_err(|e| format!("Failed to execute {}: {}", command, e))?;
+
+        if !status.success() {
+            return Err(format!("{} failed with status: {}", command, status));
+        }
+
+        Ok(())
+    }
+
+    pub fn process_alive(pid: u32) -> bool {
+        if cfg!(target_os = "windows") {
+            let output = Command::new("tasklist")
+                .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+                .output();
+
+            match output {
+                Ok(result) => String::from_utf8_lossy(&result.stdout).contains(&pid.to_string()),
+                Err(_) => false,
+            }
+        } else {
+            // Zombies are already dead, they are only waiting for the parent
+            match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+                Ok(stat) => !stat
+                    .rsplit(')')
+                    .next()
+                    .is_some_and(|rest| rest.trim_start().starts_with('Z')),
+                Err(_) => false,
+            }
+        }
+    }
+
+    pub fn terminate_process(pid: u32) -> Result<(), String> {
+        let status = if cfg!(target_os = "windows") {
+            Command::new("taskkill")
+                .args(["/PID", &pid.to_string()])
+                .status()
+        } else {
+            Command::new("kill")
+                .args(["-TERM", &pid.to_string()])
+                .status()
+        }
+        .map_err(|e| format!("Failed to stop process {}: {}", pid, e))?;
+
+        if !status.success() {
+            return Err(format!("Failed to stop process {}: {}", pid, status));
+        }
+
+        Ok(())
+    }
+
     pub fn move_item(from: &Path, to: &Path) -> io::Result<()> {
-        let from_path: &Path = from.as_ref();
-        let to_dir: &Path = to.as_ref();
+        let from_path: &Path = from;
+        let to_dir: &Path = to;
 
         let file_name = from_path
             .file_name()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Invalid source path"))?;
+            .ok_or_else(|| io::Error::other("Invalid source path"))?;
 
         let to_path: PathBuf = to_dir.join(file_name);
 
-        if let Some(parent) = to_path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent)?;
-            }
+        if let Some(parent) = to_path.parent()
+            && !parent.exists()
+        {
+            fs::create_dir_all(parent)?;
         }
 
         if to_path.exists() {
@@ -66,71 +167,273 @@ impl Utils {
         Ok(())
     }
 
-    pub fn move_items(from: &Path, to: &Path) -> io::Result<()> {
-        if !from.exists() || !from.is_dir() {
-            return Err(io::Error::new(io::ErrorKind::NotFound, "Invalid path"));
+    /// Moves the contents of a folder into another one, existing folders are merged instead
+    /// of replaced so the files added by the user inside them are kept
+    pub fn merge_items(from: &Path, to: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(from)? {
+            Utils::merge_item(&entry?.path(), to)?;
         }
 
-        if !to.exists() || !to.is_dir() {
-            return Err(io::Error::new(io::ErrorKind::NotFound, "Invalid path"));
+        Ok(())
+    }
+
+    /// Moves an item into a folder, merging it with an existing folder of the same name
+    pub fn merge_item(source: &Path, to: &Path) -> io::Result<()> {
+        let target: PathBuf = match source.file_name() {
+            Some(name) => to.join(name),
+            None => return Err(io::Error::other("Invalid source path")),
+        };
+
+        if source.is_dir() && target.is_dir() {
+            Utils::merge_items(source, &target)?;
+            fs::remove_dir(source)
+        } else {
+            Utils::move_item(source, to)
         }
+    }
+
+    /// Working path from --working-path, the VINTAGE_STORY variable or the executable folder,
+    /// the color tells the user which one was used
+    pub fn get_working_path(
+        working_path: &Option<String>,
+    ) -> Result<(PathBuf, colored::Color), String> {
+        if let Some(path) = working_path {
+            let path_buf: PathBuf = Path::new(path).to_path_buf();
 
-        for entry_result in fs::read_dir(from)? {
-            let entry = entry_result?;
-            let path = entry.path();
-            let _ = Utils::move_item(path.as_path(), to);
+            if path_buf.exists() && path_buf.is_dir() {
+                return Ok((path_buf, colored::Color::Green));
+            } else {
+                return Err(format!("The working-path: {}, is invalid", path));
+            }
         }
 
-        Ok(())
+        match env::var("VINTAGE_STORY") {
+            Ok(value) => Ok((PathBuf::from(value), colored::Color::BrightGreen)),
+            Err(_) => Ok((
+                env::current_exe()
+                    .ok()
+                    .and_then(|exe_path: PathBuf| exe_path.parent().map(|p: &Path| p.to_path_buf()))
+                    .unwrap_or_else(|| PathBuf::from(".")),
+                colored::Color::Yellow,
+            )),
+        }
     }
 
-    pub fn get_game_version(working_path: &Path) -> Option<String> {
-        let assets_dir: PathBuf = working_path.join("assets");
+    /// Working path of the arguments for the commands that do not print it
+    pub fn working_path(working_path: &Option<String>) -> Result<PathBuf, UpdaterError> {
+        Utils::get_working_path(working_path)
+            .map(|(path, _)| path)
+            .map_err(UpdaterError::InvalidPath)
+    }
 
-        if !assets_dir.exists() || !assets_dir.is_dir() {
-            return None;
-        }
+    /// Names of the assets/version-* files without prefix and extension, more than one is
+    /// left by an update that did not finish
+    fn version_files(working_path: &Path) -> Vec<String> {
+        let entries: fs::ReadDir = match fs::read_dir(working_path.join("assets")) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_name: String = entry.file_name().to_string_lossy().to_string();
+                file_name
+                    .strip_prefix("version-")
+                    .map(|version| version.trim_end_matches(".txt").to_string())
+            })
+            .collect()
+    }
 
-        let entries: fs::ReadDir = fs::read_dir(&assets_dir).ok()?;
+    /// Version the game binaries were built with, read from the deps.json of the server or
+    /// the client
+    fn get_binary_version(working_path: &Path) -> Option<GameVersion> {
+        let deps_files: [&str; 2] = ["VintagestoryServer.deps.json", "Vintagestory.deps.json"];
+        let assemblies: [&str; 4] = [
+            "VintagestoryAPI",
+            "VintagestoryLib",
+            "VintagestoryServer",
+            "Vintagestory",
+        ];
+
+        for deps_file in deps_files {
+            let contents: String = match fs::read_to_string(working_path.join(deps_file)) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+            let deps: serde_json::Value = match serde_json::from_str(&contents) {
+                Ok(deps) => deps,
+                Err(_) => continue,
+            };
+            let libraries = match deps.get("libraries").and_then(|value| value.as_object()) {
+                Some(libraries) => libraries,
+                None => continue,
+            };
+
+            for key in libraries.keys() {
+                let (name, version) = match key.split_once('/') {
+                    Some(parts) => parts,
+                    None => continue,
+                };
+                if !assemblies.contains(&name) {
+                    continue;
+                }
 
-        for entry in entries {
-            let entry: fs::DirEntry = entry.ok()?;
-            let file_name: std::ffi::OsString = entry.file_name();
-            let file_name_str: std::borrow::Cow<'_, str> = file_name.to_string_lossy();
+                // Assembly versions have four numbers, 1.21.5.0 is the game 1.21.5
+                let version: &str = match version.matches('.').count() {
+                    3 => version.rsplit_once('.').map_or(version, |(start, _)| start),
+                    _ => version,
+                };
 
-            if file_name_str.starts_with("version-") {
-                let version: String = file_name_str.trim_start_matches("version-").to_string();
-                return Some(version);
+                // 1.0.0 is the .NET default of an assembly without version, not a game release
+                match version.parse::<GameVersion>() {
+                    Ok(version) if version.to_string() != "1.0.0" => return Some(version),
+                    _ => continue,
+                }
             }
         }
 
         None
     }
 
-    pub fn get_game_type(game_type: &String) -> String {
+    /// Installed version from the highest assets/version-* file, cross-checked with the
+    /// version of the game binaries, which is used when there is no version file
+    pub fn get_game_version(working_path: &Path) -> Option<GameVersion> {
+        let files: Vec<String> = Utils::version_files(working_path);
+
+        let mut versions: Vec<GameVersion> = Vec::new();
+        for file in &files {
+            match file.parse::<GameVersion>() {
+                Ok(version) => versions.push(version),
+                Err(e) => LogsInstance::warn(
+                    format!("Ignoring the version file version-{}: {}", file, e).as_str(),
+                    colored::Color::Yellow,
+                ),
+            }
+        }
+        versions.sort();
+
+        if versions.len() > 1 {
+            let names: Vec<String> = versions.iter().map(|version| version.to_string()).collect();
+            LogsInstance::warn(
+                format!(
+                    "Found {} version files in assets: {}, probably left by an update that did not finish, using the highest",
+                    versions.len(),
+                    names.join(", ")
+                )
+                .as_str(),
+                colored::Color::Yellow,
+            );
+        }
+
+        let file_version: Option<GameVersion> = versions.pop();
+        let binary_version: Option<GameVersion> = Utils::get_binary_version(working_path);
+
+        match (file_version, binary_version) {
+            (Some(file_version), Some(binary_version)) => {
+                // The assembly version of deps.json has no pre-release part, rc builds of the
+                // same release are not a mismatch
+                let release = |version: &GameVersion| -> (u32, u32, u32) {
+                    (version.major(), version.minor(), version.patch())
+                };
+                if release(&file_version) != release(&binary_version) {
+                    LogsInstance::warn(
+                        format!(
+                            "The version file says {} but the game binaries are {}, the install might be broken",
+                            file_version, binary_version
+                        )
+                        .as_str(),
+                        colored::Color::Yellow,
+                    );
+                }
+                Some(file_version)
+            }
+            (Some(file_version), None) => Some(file_version),
+            (None, Some(binary_version)) => {
+                LogsInstance::warn(
+                    format!(
+                        "No version file found, using the version of the game binaries: {}",
+                        binary_version
+                    )
+                    .as_str(),
+                    colored::Color::Yellow,
+                );
+                Some(binary_version)
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Prefix of the archive names for the game type, the windows client only has an installer
+    pub fn get_game_type(game_type: &str) -> Result<String, String> {
         if cfg!(target_os = "windows") {
             if game_type == "client" {
-                LogsInstance::print(
+                return Err(String::from(
                     "This update tool does not support windows client update, because there is only .exe installer in official repositories",
-                    colored::Color::BrightRed,
-                );
-                std::process::exit(1);
+                ));
             } else if game_type == "server" {
-                return String::from("vs_server_win-x64_");
+                return Ok(String::from("vs_server_win-x64_"));
             }
         } else if cfg!(target_os = "linux") {
             if game_type == "client" {
-                return String::from("vs_client_linux-x64_");
+                return Ok(String::from("vs_client_linux-x64_"));
             } else if game_type == "server" {
-                return String::from("vs_server_linux-x64_");
+                return Ok(String::from("vs_server_linux-x64_"));
             }
         }
 
-        LogsInstance::print("Unknown system or game type", colored::Color::BrightRed);
-        std::process::exit(1);
+        Err(String::from("Unknown system or game type"))
+    }
+
+    /// Versions published by the game api, the unstable list has the pre-releases and
+    /// release candidates
+    pub fn get_published_versions(unstable: bool) -> Result<Vec<GameVersion>, String> {
+        let url: &str = if unstable {
+            "https://api.vintagestory.at/unstable.json"
+        } else {
+            "https://api.vintagestory.at/stable.json"
+        };
+
+        let body: String =
+            Utils::url_result(url).ok_or_else(|| format!("Failed to query: {}", url))?;
+        let versions: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&body)
+            .map_err(|e| format!("Invalid version list {}: {}", url, e))?;
+
+        Ok(versions
+            .keys()
+            .filter_map(|version| version.parse().ok())
+            .collect())
     }
 
-    pub fn patch_arm64(working_path: &Path, version: &str) -> Result<(), String> {
+    pub fn arm64_url(version: &str) -> String {
+        format!(
+            "https://github.com/anegostudios/VintagestoryServerArm64/releases/download/{}/vs_server_linux-arm64_{}.tar.gz",
+            version, version
+        )
+    }
+
+    /// Replaces the x64 binaries with the ARM64 build of the version, `exact` refuses to fall
+    /// back to the latest ARM64 build when that version has none
+    pub fn patch_arm64(working_path: &Path, version: &str, exact: bool) -> Result<(), String> {
+        // Resolve which ARM64 version to download — exact match or latest available, checked
+        // before removing the x64 binaries
+        let arm64_version = if Utils::url_exists(&Utils::arm64_url(version)) {
+            version.to_string()
+        } else if exact {
+            return Err(format!("ARM64 release for {} not found", version));
+        } else {
+            LogsInstance::warn(
+                format!(
+                    "ARM64 release for {} not found, fetching latest available...",
+                    version
+                )
+                .as_str(),
+                colored::Color::Yellow,
+            );
+            Utils::get_latest_arm64_release()
+                .ok_or_else(|| "Failed to fetch latest ARM64 release from GitHub".to_string())?
+        };
+
         // Remove x64-only binaries
         let x64_files = [
             "VintagestoryServer",
@@ -150,25 +453,7 @@ impl Utils {
             let _ = fs::remove_dir_all(&lib_path);
         }
 
-        // Resolve which ARM64 version to download — exact match or latest available
-        let arm64_version = if Utils::url_exists(&format!(
-            "https://github.com/anegostudios/VintagestoryServerArm64/releases/download/{}/vs_server_linux-arm64_{}.tar.gz",
-            version, version
-        )) {
-            version.to_string()
-        } else {
-            LogsInstance::print(
-                format!("ARM64 release for {} not found, fetching latest available...", version).as_str(),
-                colored::Color::Yellow,
-            );
-            Utils::get_latest_arm64_release()
-                .ok_or_else(|| "Failed to fetch latest ARM64 release from GitHub".to_string())?
-        };
-
-        let arm64_url = format!(
-            "https://github.com/anegostudios/VintagestoryServerArm64/releases/download/{}/vs_server_linux-arm64_{}.tar.gz",
-            arm64_version, arm64_version
-        );
+        let arm64_url: String = Utils::arm64_url(&arm64_version);
 
         LogsInstance::print(
             format!("Downloading ARM64 binaries: {}", arm64_url).as_str(),
@@ -181,22 +466,12 @@ impl Utils {
 
         let parent_dir = arm64_path.parent().unwrap_or_else(|| Path::new("."));
 
-        let status = Command::new("tar")
-            .arg("-xzf")
-            .arg(arm64_path.to_str().ok_or("Invalid file path")?)
-            .arg("-C")
-            .arg(parent_dir.to_str().ok_or("Invalid parent directory")?)
-            .status()
-            .map_err(|e| format!("Failed to execute tar: {}", e))?;
-
-        if !status.success() {
-            return Err(format!("Tar command failed with status: {}", status));
-        }
+        Utils::extract_tar(&arm64_path, parent_dir, None)?;
 
         // Move server/* to working_path
         let server_dir = parent_dir.join("server");
         if server_dir.exists() {
-            let _ = Utils::move_items(&server_dir, working_path);
+            let _ = Utils::merge_items(&server_dir, working_path);
             let _ = fs::remove_dir_all(&server_dir);
         }
 
@@ -205,25 +480,21 @@ impl Utils {
         Ok(())
     }
 
+    /// Other systems use the linux commands, the game type already refuses them
     pub fn get_compress_type() -> String {
         if cfg!(target_os = "windows") {
             String::from(".zip")
-        } else if cfg!(target_os = "linux") {
-            String::from(".tar.gz")
         } else {
-            LogsInstance::print("Unkown system", colored::Color::BrightRed);
-            std::process::exit(1)
+            String::from(".tar.gz")
         }
     }
 
     pub fn url_exists(url: &str) -> bool {
+        LogsInstance::trace(format!("Checking url: {}", url).as_str());
         if cfg!(target_os = "windows") {
             Utils::url_exists_windows(url)
-        } else if cfg!(target_os = "linux") {
-            Utils::url_exists_linux(url)
         } else {
-            LogsInstance::print("Unkown system", colored::Color::BrightRed);
-            std::process::exit(1)
+            Utils::url_exists_linux(url)
         }
     }
 
@@ -251,14 +522,119 @@ impl Utils {
         }
     }
 
+    /// Size announced by the server for a download, None when it does not send one
+    fn remote_size(url: &str) -> Option<u64> {
+        let output = if cfg!(target_os = "windows") {
+            let cmd: String = format!(
+                "(Invoke-WebRequest -Uri '{}' -Method Head -UseBasicParsing).Headers['Content-Length']",
+                url
+            );
+            Command::new("powershell").args(["-Command", &cmd]).output()
+        } else {
+            Command::new("wget")
+                .args(["--spider", "--server-response", "--quiet"])
+                .arg(url)
+                .output()
+        }
+        .ok()?;
+
+        // wget prints the headers of every redirect to stderr, the last one is the file
+        let text: String = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        text.lines().rev().find_map(|line| {
+            let line: &str = line.trim();
+            match line.split_once(':') {
+                Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                    value.trim().parse().ok()
+                }
+                _ => line.parse().ok(),
+            }
+        })
+    }
+
+    /// Waits for a download running in the background, the progress is the size of the file
+    fn wait_download(mut child: Child, save_path: &Path, url: &str) -> io::Result<ExitStatus> {
+        let label: &str = url.rsplit('/').next().unwrap_or(url);
+        let mut progress: Progress = Progress::bytes(label, Utils::remote_size(url));
+
+        let status: ExitStatus = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            progress.update(fs::metadata(save_path).map(|m| m.len()).unwrap_or(0));
+            thread::sleep(Duration::from_millis(200));
+        };
+
+        if status.success() {
+            progress.update(fs::metadata(save_path).map(|m| m.len()).unwrap_or(0));
+            progress.finish();
+        }
+
+        Ok(status)
+    }
+
+    /// Runs an extraction that prints a line for each extracted entry, the lines accepted by
+    /// is_file are counted as the progress
+    fn run_extraction(
+        command: &mut Command,
+        total: Option<usize>,
+        is_file: &dyn Fn(&str) -> bool,
+    ) -> io::Result<ExitStatus> {
+        let mut child: Child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let mut progress: Progress = Progress::files("Extracting", total.map(|total| total as u64));
+        let mut extracted: u64 = 0;
+
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                LogsInstance::trace(line.trim());
+                if is_file(&line) {
+                    extracted += 1;
+                    progress.update(extracted);
+                }
+            }
+        }
+
+        let status: ExitStatus = child.wait()?;
+        if status.success() {
+            progress.finish();
+        }
+
+        Ok(status)
+    }
+
+    /// Extracts a tar.gz into a folder
+    fn extract_tar(archive: &Path, destination: &Path, total: Option<usize>) -> Result<(), String> {
+        let status: ExitStatus = Utils::run_extraction(
+            Command::new("tar")
+                .arg("-xvzf")
+                .arg(archive.to_str().ok_or("Invalid file path")?)
+                .arg("-C")
+                .arg(destination.to_str().ok_or("Invalid parent directory")?),
+            total,
+            &|line| !line.trim_end().ends_with('/'),
+        )
+        .map_err(|e| format!("Failed to execute tar: {}", e))?;
+
+        if !status.success() {
+            return Err(format!("Tar command failed with status: {}", status));
+        }
+
+        Ok(())
+    }
+
     pub fn url_result(url: &str) -> Option<String> {
+        LogsInstance::trace(format!("Requesting: {}", url).as_str());
         if cfg!(target_os = "windows") {
             Utils::url_result_windows(url)
-        } else if cfg!(target_os = "linux") {
-            Utils::url_result_linux(url)
         } else {
-            LogsInstance::print("Unkown system", colored::Color::BrightRed);
-            std::process::exit(1)
+            Utils::url_result_linux(url)
         }
     }
 
@@ -310,51 +686,184 @@ impl Utils {
             .collect()
     }
 
-    pub fn check_temp_folder(working_path: &Path) {
+    pub fn url_encode(text: &str) -> String {
+        let mut encoded: String = String::new();
+        for byte in text.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                    encoded.push(byte as char)
+                }
+                _ => encoded.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        encoded
+    }
+
+    pub fn truncate(text: &str, max: usize) -> String {
+        if text.chars().count() <= max {
+            return text.to_string();
+        }
+        let mut truncated: String = text.chars().take(max.saturating_sub(3)).collect();
+        truncated.push_str("...");
+        truncated
+    }
+
+    /// Handles a .temp folder left without a journal, by an older version or a crash before
+    /// the journal was written, the user is asked when there is no policy and nothing is
+    /// deleted unless asked explicitly
+    pub fn check_temp_folder(
+        working_path: &Path,
+        policy: Option<LeftoverTemp>,
+    ) -> Result<(), String> {
         let temp_path = Path::new(working_path).join(".temp");
 
-        if temp_path.exists() && temp_path.is_dir() {
-            LogsInstance::print(
-                "The folder .temp already exists, probably the updater tool exited before completing.",
-                colored::Color::Yellow,
-            );
-            LogsInstance::print("Do you want to delete it? (y,N): ", colored::Color::Yellow);
+        if !temp_path.exists() || !temp_path.is_dir() {
+            return Ok(());
+        }
 
-            if let Err(e) = io::stdout().flush() {
+        LogsInstance::warn(
+            "The folder .temp already exists, probably the updater tool exited before completing.",
+            colored::Color::Yellow,
+        );
+
+        let policy: LeftoverTemp = match policy {
+            Some(policy) => policy,
+            None => Utils::ask_temp_policy()?,
+        };
+
+        match policy {
+            LeftoverTemp::Restore => {
                 LogsInstance::print(
-                    format!("Failed to flush stdout: {}", e).as_str(),
-                    colored::Color::BrightRed,
+                    "Moving .temp back to the working path...",
+                    colored::Color::White,
                 );
-                return;
-            }
-
-            let mut input: String = String::new();
-            match io::stdin().read_line(&mut input) {
-                Ok(_) => {
-                    let input: String = input.trim().to_lowercase();
-                    if input == "y" {
-                        match fs::remove_dir_all(&temp_path) {
-                            Ok(_) => {}
-                            Err(e) => {
-                                LogsInstance::print(
-                                    format!("Failed to delete folder: {}", e).as_str(),
-                                    colored::Color::BrightRed,
-                                );
-                                return;
-                            }
-                        }
-                    } else {
-                        std::process::exit(0);
-                    }
-                }
-                Err(e) => {
-                    LogsInstance::print(
-                        format!("Failed to read input: {}", e).as_str(),
-                        colored::Color::BrightRed,
-                    );
-                    return;
-                }
+                Utils::restore_temp(&temp_path, working_path)
             }
+            LeftoverTemp::Discard => {
+                LogsInstance::print("Deleting .temp...", colored::Color::Yellow);
+                fs::remove_dir_all(&temp_path)
+                    .map_err(|e| format!("Failed to delete folder: {}", e))
+            }
+            LeftoverTemp::Abort => Err(String::from(
+                "Update aborted, the folder .temp was left untouched, use --leftover-temp restore or discard",
+            )),
+        }
+    }
+
+    fn ask_temp_policy() -> Result<LeftoverTemp, String> {
+        LogsInstance::warn(
+            "Type 'r' to move its contents back to the working path, 'delete' to delete it, anything else aborts: ",
+            colored::Color::Yellow,
+        );
+
+        io::stdout()
+            .flush()
+            .map_err(|e| format!("Failed to flush stdout: {}", e))?;
+
+        let mut input: String = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .map_err(|e| format!("Failed to read input: {}", e))?;
+
+        Ok(match input.trim().to_lowercase().as_str() {
+            "r" => LeftoverTemp::Restore,
+            "delete" => LeftoverTemp::Discard,
+            _ => LeftoverTemp::Abort,
+        })
+    }
+
+    /// UTC date and time from a system time as (year, month, day, hour, minute, second)
+    pub fn utc_datetime(time: SystemTime) -> (i64, u32, u32, u32, u32, u32) {
+        let secs: i64 = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let days: i64 = secs.div_euclid(86400);
+        let rem: i64 = secs.rem_euclid(86400);
+
+        // Days to civil date, from Howard Hinnant's date algorithms
+        let z: i64 = days + 719468;
+        let era: i64 = z.div_euclid(146097);
+        let doe: i64 = z - era * 146097;
+        let yoe: i64 = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy: i64 = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp: i64 = (5 * doy + 2) / 153;
+        let day: u32 = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month: u32 = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year: i64 = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        (
+            year,
+            month,
+            day,
+            (rem / 3600) as u32,
+            (rem % 3600 / 60) as u32,
+            (rem % 60) as u32,
+        )
+    }
+
+    /// Unix seconds of the start of a UTC date written as 2025-01-31
+    pub fn parse_date(text: &str) -> Option<u64> {
+        let mut parts = text.trim().splitn(3, '-');
+        let year: i64 = parts.next()?.parse().ok()?;
+        let month: i64 = parts.next()?.parse().ok()?;
+        let day: i64 = parts.next()?.parse().ok()?;
+
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        // Civil date to days, the inverse of utc_datetime
+        let year: i64 = if month <= 2 { year - 1 } else { year };
+        let era: i64 = year.div_euclid(400);
+        let yoe: i64 = year - era * 400;
+        let mp: i64 = if month > 2 { month - 3 } else { month + 9 };
+        let doy: i64 = (153 * mp + 2) / 5 + day - 1;
+        let doe: i64 = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days: i64 = era * 146097 + doe - 719468;
+
+        u64::try_from(days * 86400).ok()
+    }
+
+    /// Timestamp safe for file names: 20250131-235959
+    pub fn file_timestamp(time: SystemTime) -> String {
+        let (year, month, day, hour, minute, second) = Utils::utc_datetime(time);
+        format!(
+            "{:04}{:02}{:02}-{:02}{:02}{:02}",
+            year, month, day, hour, minute, second
+        )
+    }
+
+    pub fn format_datetime(time: SystemTime) -> String {
+        let (year, month, day, hour, minute, second) = Utils::utc_datetime(time);
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            year, month, day, hour, minute, second
+        )
+    }
+
+    /// Parses durations like 30s, 5m or 1h, numbers without unit are minutes
+    pub fn parse_duration(text: &str) -> Option<u64> {
+        let text: &str = text.trim();
+        let (number, multiplier): (&str, u64) = if let Some(number) = text.strip_suffix('s') {
+            (number, 1)
+        } else if let Some(number) = text.strip_suffix('m') {
+            (number, 60)
+        } else if let Some(number) = text.strip_suffix('h') {
+            (number, 3600)
+        } else {
+            (text, 60)
+        };
+
+        number.trim().parse::<u64>().ok().map(|n| n * multiplier)
+    }
+
+    pub fn format_duration(seconds: u64) -> String {
+        if seconds >= 60 && seconds.is_multiple_of(60) {
+            let minutes: u64 = seconds / 60;
+            format!("{} minute{}", minutes, if minutes == 1 { "" } else { "s" })
+        } else {
+            format!("{} second{}", seconds, if seconds == 1 { "" } else { "s" })
         }
     }
 
@@ -373,7 +882,333 @@ impl Utils {
         }
     }
 
-    pub fn clean_working_path(working_path: &Path) -> io::Result<()> {
+    /// Files and folders of the tool itself, never deleted or listed in the manifest
+    pub fn is_tool_item(name: &OsStr) -> bool {
+        name == journal::TEMP_FOLDER
+            || name == journal::JOURNAL_FILE
+            || name == format!("{}.partial", journal::JOURNAL_FILE).as_str()
+            || name == lock::LOCK_FILE
+            || name == MANIFEST_FILE
+            || name == state::STATE_FILE
+            || name == format!("{}.partial", state::STATE_FILE).as_str()
+            || name == history::HISTORY_FILE
+            || name == logger::LOGS_FOLDER
+    }
+
+    /// Refuses the filesystem root, the home folder and system folders, a wrong working
+    /// path would delete them entirely
+    pub fn check_protected_path(working_path: &Path) -> Result<(), String> {
+        let canonical: PathBuf = working_path
+            .canonicalize()
+            .map_err(|e| format!("Invalid working path {}: {}", working_path.display(), e))?;
+
+        let home: Option<PathBuf> = env::var_os(if cfg!(target_os = "windows") {
+            "USERPROFILE"
+        } else {
+            "HOME"
+        })
+        .map(PathBuf::from)
+        .and_then(|home| home.canonicalize().ok());
+
+        let protected: bool = canonical.parent().is_none()
+            || home.as_ref() == Some(&canonical)
+            || PROTECTED_PATHS
+                .iter()
+                .any(|path| Path::new(path).canonicalize().ok().as_ref() == Some(&canonical));
+
+        if protected {
+            return Err(format!(
+                "Refusing to clean {}, set --working-path to the Vintage Story folder",
+                canonical.display()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the working path holds a Vintage Story install before anything is deleted
+    pub fn check_install_path(working_path: &Path) -> Result<(), String> {
+        Utils::check_protected_path(working_path)?;
+
+        if Utils::has_manifest(working_path) || !Utils::version_files(working_path).is_empty() {
+            return Ok(());
+        }
+
+        let has_server: bool = fs::read_dir(working_path)
+            .map(|entries| {
+                entries.flatten().any(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with("VintagestoryServer")
+                })
+            })
+            .unwrap_or(false);
+
+        if !has_server {
+            return Err(format!(
+                "{} does not look like a Vintage Story install (no assets/version-*.txt or VintagestoryServer), refusing to clean it",
+                working_path.display()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Every file of the working path relative to it with / separators, the tool files and
+    /// the executable are skipped
+    pub fn list_install_files(working_path: &Path) -> Vec<String> {
+        let exe_path: PathBuf = std::env::current_exe().unwrap_or_default();
+        let exe_canonical: PathBuf = exe_path.canonicalize().unwrap_or(exe_path);
+
+        let mut files: Vec<String> = Vec::new();
+        let mut pending: Vec<PathBuf> = vec![working_path.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let entries: fs::ReadDir = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                let path: PathBuf = entry.path();
+
+                if dir == working_path && Utils::is_tool_item(&entry.file_name()) {
+                    continue;
+                }
+
+                if path.canonicalize().ok().as_ref() == Some(&exe_canonical) {
+                    continue;
+                }
+
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    pending.push(path);
+                } else if let Ok(relative) = path.strip_prefix(working_path) {
+                    files.push(relative.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        }
+
+        files.sort();
+        files
+    }
+
+    /// Writes the files of the new install: the files of the archive and the files added
+    /// during the install, like the ARM64 binaries, the excluded files are not part of it
+    pub fn write_manifest(
+        working_path: &Path,
+        archive_files: &[String],
+        exclude: &[String],
+    ) -> Result<(), String> {
+        let exclude: HashSet<&String> = exclude.iter().collect();
+        let mut files: Vec<String> = Utils::list_install_files(working_path)
+            .into_iter()
+            .filter(|file| !exclude.contains(file))
+            .chain(archive_files.iter().cloned())
+            .collect();
+        files.sort();
+        files.dedup();
+
+        fs::write(working_path.join(MANIFEST_FILE), files.join("\n"))
+            .map_err(|e| format!("Cannot write the install manifest: {}", e))
+    }
+
+    /// Files inside the game archive, relative to the working path after the extraction
+    pub fn list_archive(archive: &Path) -> Result<Vec<String>, String> {
+        let is_zip: bool = archive
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+
+        let output = if cfg!(target_os = "windows") {
+            let cmd: String = format!(
+                "Add-Type -AssemblyName System.IO.Compression.FileSystem; \
+                 $zip = [IO.Compression.ZipFile]::OpenRead('{}'); \
+                 $zip.Entries | ForEach-Object {{ $_.FullName }}; \
+                 $zip.Dispose()",
+                archive.display()
+            );
+            Command::new("powershell").args(["-Command", &cmd]).output()
+        } else if is_zip {
+            Command::new("unzip").arg("-Z1").arg(archive).output()
+        } else {
+            Command::new("tar").arg("-tzf").arg(archive).output()
+        }
+        .map_err(|e| format!("Failed to list the archive: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "Listing the archive failed with status: {}",
+                output.status
+            ));
+        }
+
+        // The extraction moves the vintagestory folder of tar.gz and Windows archives to the
+        // working path
+        let strip_root: bool = cfg!(target_os = "windows") || !is_zip;
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().replace('\\', "/"))
+            .map(|line| line.trim_start_matches("./").to_string())
+            .filter(|line| !line.is_empty() && !line.ends_with('/'))
+            .map(|line| match line.strip_prefix("vintagestory/") {
+                Some(inner) if strip_root => inner.to_string(),
+                _ => line,
+            })
+            .collect())
+    }
+
+    /// SHA-256 of a file in lowercase hex, None when the system tool is missing
+    pub fn file_sha256(path: &Path) -> Option<String> {
+        let output = if cfg!(target_os = "windows") {
+            let cmd: String = format!(
+                "(Get-FileHash -Algorithm SHA256 -LiteralPath '{}').Hash",
+                path.display()
+            );
+            Command::new("powershell").args(["-Command", &cmd]).output()
+        } else {
+            Command::new("sha256sum").arg(path).output()
+        }
+        .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .map(|hash| hash.to_lowercase())
+    }
+
+    fn read_manifest(working_path: &Path) -> Option<Vec<String>> {
+        let contents: String = fs::read_to_string(working_path.join(MANIFEST_FILE)).ok()?;
+        Some(
+            contents
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+                .collect(),
+        )
+    }
+
+    /// Deletes the files of the previous install missing from the new version and the folders
+    /// left empty, the other files are replaced by the extraction
+    fn clean_from_manifest(
+        working_path: &Path,
+        manifest: &[String],
+        new_files: &[String],
+    ) -> io::Result<()> {
+        let mut folders: Vec<PathBuf> = Vec::new();
+        let new_files: HashSet<&String> = new_files.iter().collect();
+
+        for file in manifest.iter().filter(|file| !new_files.contains(file)) {
+            let relative: &Path = Path::new(file);
+
+            // A manifest edited by hand must not reach outside the working path
+            if relative
+                .components()
+                .any(|component| !matches!(component, std::path::Component::Normal(_)))
+            {
+                LogsInstance::warn(
+                    format!("Invalid manifest entry ignored: {}", file).as_str(),
+                    colored::Color::Yellow,
+                );
+                continue;
+            }
+
+            if relative
+                .components()
+                .next()
+                .is_some_and(|first| Utils::is_tool_item(first.as_os_str()))
+            {
+                continue;
+            }
+
+            let path: PathBuf = working_path.join(relative);
+            LogsInstance::trace(format!("Removing: {}", path.display()).as_str());
+            if let Err(e) = fs::remove_file(&path)
+                && e.kind() != io::ErrorKind::NotFound
+            {
+                LogsInstance::warn(
+                    format!("Failed to remove file {}: {}", path.display(), e).as_str(),
+                    colored::Color::Yellow,
+                );
+            }
+
+            let mut parent: Option<&Path> = path.parent();
+            while let Some(folder) = parent {
+                if folder == working_path || folders.iter().any(|known| known == folder) {
+                    break;
+                }
+                folders.push(folder.to_path_buf());
+                parent = folder.parent();
+            }
+        }
+
+        // Deepest first so the parents are empty when reached
+        folders.sort_by_key(|folder| std::cmp::Reverse(folder.components().count()));
+        for folder in folders {
+            let _ = fs::remove_dir(folder);
+        }
+
+        Ok(())
+    }
+
+    pub fn has_manifest(working_path: &Path) -> bool {
+        working_path.join(MANIFEST_FILE).is_file()
+    }
+
+    /// True when a folder holds a world at any depth, a server data folder whatever its name
+    fn contains_world_data(path: &Path) -> bool {
+        let mut pending: Vec<PathBuf> = vec![path.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            let entries: fs::ReadDir = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                let name: std::ffi::OsString = entry.file_name();
+                if name == "serverconfig.json" || name == "Saves" {
+                    return true;
+                }
+                if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                    pending.push(entry.path());
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Deletes the files of the previous install, only the files of the manifest unless
+    /// unmanaged allows an install without manifest to be cleaned entirely
+    pub fn clean_working_path(
+        working_path: &Path,
+        new_files: &[String],
+        archive: &Path,
+        unmanaged: bool,
+    ) -> io::Result<()> {
+        Utils::check_protected_path(working_path).map_err(io::Error::other)?;
+
+        if let Some(manifest) = Utils::read_manifest(working_path) {
+            return Utils::clean_from_manifest(working_path, &manifest, new_files);
+        }
+
+        if !unmanaged {
+            return Err(io::Error::other(format!(
+                "No install manifest in {}, use --clean-unmanaged to delete everything except the ignored items",
+                working_path.display()
+            )));
+        }
+
+        LogsInstance::warn(
+            "No install manifest found, everything except the ignored items is deleted",
+            colored::Color::Yellow,
+        );
+
         let working_path: &Path = Path::new(working_path);
         let exe_path: PathBuf = std::env::current_exe().unwrap_or_default();
         let exe_canonical: PathBuf = exe_path.canonicalize().unwrap_or(exe_path.clone());
@@ -383,8 +1218,8 @@ impl Utils {
             let path: PathBuf = entry.path();
             let item_name: std::ffi::OsString = entry.file_name();
 
-            // Ignore .temp folder
-            if item_name == ".temp" {
+            // Ignore .temp folder and the other tool files
+            if Utils::is_tool_item(&item_name) {
                 continue;
             }
 
@@ -394,16 +1229,30 @@ impl Utils {
                 continue;
             }
 
+            // Ignore the archive of the new version
+            if path == archive {
+                continue;
+            }
+
+            // The saves are never deleted, even in a folder that was not ignored
+            if path.is_dir() && Utils::contains_world_data(&path) {
+                LogsInstance::warn(
+                    format!("Keeping {}, it contains world data", path.display()).as_str(),
+                    colored::Color::Yellow,
+                );
+                continue;
+            }
+
             if path.is_dir() {
                 if let Err(e) = fs::remove_dir_all(&path) {
-                    LogsInstance::print(
+                    LogsInstance::warn(
                         format!("Failed to remove directory {}: {}", path.display(), e).as_str(),
                         colored::Color::Yellow,
                     );
                 }
             } else {
                 if let Err(e) = fs::remove_file(&path) {
-                    LogsInstance::print(
+                    LogsInstance::warn(
                         format!("Failed to remove file {}: {}", path.display(), e).as_str(),
                         colored::Color::Yellow,
                     );
@@ -415,13 +1264,11 @@ impl Utils {
     }
 
     pub fn download_file(url: &str, working_path: &Path) -> Result<PathBuf, String> {
+        LogsInstance::debug(format!("Downloading {} to {}", url, working_path.display()).as_str());
         if cfg!(target_os = "windows") {
             Utils::download_file_windows(url, working_path)
-        } else if cfg!(target_os = "linux") {
-            Utils::download_file_linux(url, working_path)
         } else {
-            LogsInstance::print("Unkown system", colored::Color::BrightRed);
-            std::process::exit(1)
+            Utils::download_file_linux(url, working_path)
         }
     }
 
@@ -434,14 +1281,17 @@ impl Utils {
             ));
         }
 
-        let file_name: &str = url.split('/').last().unwrap_or("invalid_file_name");
+        let file_name: &str = url.split('/').next_back().unwrap_or("invalid_file_name");
         let save_path: PathBuf = working_path.join(file_name);
 
-        let status = Command::new("wget")
+        let child: Child = Command::new("wget")
+            .arg("-q")
             .arg(url)
             .arg("-O")
             .arg(save_path.to_str().unwrap())
-            .status()
+            .spawn()
+            .map_err(|e| format!("Failed to execute wget: {}", e))?;
+        let status: ExitStatus = Utils::wait_download(child, &save_path, url)
             .map_err(|e| format!("Failed to execute wget: {}", e))?;
 
         if !status.success() {
@@ -459,11 +1309,12 @@ impl Utils {
             ));
         }
 
-        let file_name = url.split('/').last().unwrap_or("invalid_file_name");
+        let file_name = url.split('/').next_back().unwrap_or("invalid_file_name");
         let save_path = working_path.join(file_name);
 
+        // The progress of Invoke-WebRequest is replaced by ours, it also slows the download
         let ps_command = format!(
-            "Invoke-WebRequest -Uri '{}' -OutFile '{}'",
+            "$ProgressPreference = 'SilentlyContinue'; Invoke-WebRequest -Uri '{}' -OutFile '{}'",
             url,
             save_path.display()
         );
@@ -473,9 +1324,11 @@ impl Utils {
             colored::Color::BrightWhite,
         );
 
-        let status = Command::new("powershell")
-            .args(&["-Command", &ps_command])
-            .status()
+        let child: Child = Command::new("powershell")
+            .args(["-Command", &ps_command])
+            .spawn()
+            .map_err(|e| format!("Failed to execute PowerShell: {}", e))?;
+        let status: ExitStatus = Utils::wait_download(child, &save_path, url)
             .map_err(|e| format!("Failed to execute PowerShell: {}", e))?;
 
         if !status.success() {
@@ -485,23 +1338,23 @@ impl Utils {
         Ok(save_path)
     }
 
-    pub fn uncompress(working_path: &Path) -> Result<(), String> {
+    /// Extracts an archive next to it, total_files is the number of files for the progress
+    pub fn uncompress(working_path: &Path, total_files: Option<usize>) -> Result<(), String> {
         if cfg!(target_os = "windows") {
             Utils::uncompress_windows(working_path)
-        } else if cfg!(target_os = "linux") {
-            if let Some(ext) = working_path.extension() {
-                if ext.eq_ignore_ascii_case("zip") {
-                    return Utils::uncompress_linux_zip(working_path);
-                }
-            }
-            Utils::uncompress_linux(working_path)
+        } else if let Some(ext) = working_path.extension()
+            && ext.eq_ignore_ascii_case("zip")
+        {
+            Utils::uncompress_linux_zip(working_path, total_files)
         } else {
-            LogsInstance::print("Unkown system", colored::Color::BrightRed);
-            std::process::exit(1)
+            Utils::uncompress_linux(working_path, total_files)
         }
     }
 
-    pub fn uncompress_linux(compressed_version: &Path) -> Result<(), String> {
+    pub fn uncompress_linux(
+        compressed_version: &Path,
+        total_files: Option<usize>,
+    ) -> Result<(), String> {
         if !compressed_version.exists() {
             return Err(format!(
                 "File does not exist: {}",
@@ -513,26 +1366,21 @@ impl Utils {
             .parent()
             .unwrap_or_else(|| Path::new("."));
 
-        let status = Command::new("tar")
-            .arg("-xzf")
-            .arg(compressed_version.to_str().ok_or("Invalid file path")?)
-            .arg("-C")
-            .arg(parent_dir.to_str().ok_or("Invalid parent directory")?)
-            .status()
-            .map_err(|e| format!("Failed to execute tar: {}", e))?;
-
-        if !status.success() {
-            return Err(format!("Tar command failed with status: {}", status));
-        }
+        Utils::extract_tar(compressed_version, parent_dir, total_files)?;
 
         let vintagestory_path: PathBuf = parent_dir.join("vintagestory");
 
-        let _ = Utils::move_items(&vintagestory_path, parent_dir);
+        if Utils::merge_items(&vintagestory_path, parent_dir).is_ok() {
+            let _ = fs::remove_dir(&vintagestory_path);
+        }
 
         Ok(())
     }
 
-    pub fn uncompress_linux_zip(compressed_version: &Path) -> Result<(), String> {
+    pub fn uncompress_linux_zip(
+        compressed_version: &Path,
+        total_files: Option<usize>,
+    ) -> Result<(), String> {
         if !compressed_version.exists() {
             return Err(format!(
                 "File does not exist: {}",
@@ -544,12 +1392,20 @@ impl Utils {
             .parent()
             .ok_or_else(|| "Failed to get parent directory".to_string())?;
 
-        let status = Command::new("unzip")
-            .arg(compressed_version.to_str().ok_or("Invalid file path")?)
-            .arg("-d")
-            .arg(parent_dir.to_str().ok_or("Invalid parent directory path")?)
-            .status()
-            .map_err(|e| format!("Failed to execute unzip: {}", e))?;
+        // -o because the output is read for the progress, an overwrite question would hang
+        let status: ExitStatus = Utils::run_extraction(
+            Command::new("unzip")
+                .arg("-o")
+                .arg(compressed_version.to_str().ok_or("Invalid file path")?)
+                .arg("-d")
+                .arg(parent_dir.to_str().ok_or("Invalid parent directory path")?),
+            total_files,
+            &|line| {
+                let line: &str = line.trim_start();
+                line.starts_with("inflating:") || line.starts_with("extracting:")
+            },
+        )
+        .map_err(|e| format!("Failed to execute unzip: {}", e))?;
 
         if !status.success() {
             return Err(format!("unzip command failed with status: {}", status));
@@ -596,24 +1452,61 @@ impl Utils {
 
         let vintagestory_path: PathBuf = parent_dir.join("vintagestory");
 
-        let _ = Utils::move_items(&vintagestory_path, parent_dir);
+        if Utils::merge_items(&vintagestory_path, parent_dir).is_ok() {
+            let _ = fs::remove_dir(&vintagestory_path);
+        }
 
         Ok(())
     }
 
-    pub fn clear_temp(temp_dir: &Path, working_path: &Path) {
-        match Utils::move_items(&temp_dir, &working_path) {
-            Ok(_) => {}
-            Err(e) => {
-                LogsInstance::print(
-                    format!("Failed to move temp to working path: {}", e).as_str(),
-                    colored::Color::BrightRed,
-                );
-                return;
+    pub fn read_zip_entry(zip_path: &Path, entry: &str) -> Option<String> {
+        let output = if cfg!(target_os = "windows") {
+            let cmd: String = format!(
+                "Add-Type -AssemblyName System.IO.Compression.FileSystem; \
+                 $zip = [IO.Compression.ZipFile]::OpenRead('{}'); \
+                 $entry = $zip.GetEntry('{}'); \
+                 if ($entry) {{ (New-Object IO.StreamReader($entry.Open())).ReadToEnd() }} else {{ exit 1 }}; \
+                 $zip.Dispose()",
+                zip_path.display(),
+                entry
+            );
+            Command::new("powershell").args(["-Command", &cmd]).output()
+        } else {
+            Command::new("unzip")
+                .arg("-p") // Extract to stdout
+                .arg(zip_path)
+                .arg(entry)
+                .output()
+        };
+
+        match output {
+            Ok(result) if result.status.success() => String::from_utf8(result.stdout).ok(),
+            _ => None,
+        }
+    }
+
+    /// Moves each item of the temp folder back to the working path, the folder is removed
+    /// only when every item was moved so nothing is lost when one of them fails
+    pub fn restore_temp(temp_dir: &Path, working_path: &Path) -> Result<(), String> {
+        let entries = fs::read_dir(temp_dir)
+            .map_err(|e| format!("Failed to read {}: {}", temp_dir.display(), e))?;
+        let mut errors: Vec<String> = Vec::new();
+
+        for entry in entries.flatten() {
+            if let Err(e) = Utils::merge_item(&entry.path(), working_path) {
+                errors.push(format!("{}: {}", entry.file_name().to_string_lossy(), e));
             }
         }
 
-        let _ = std::fs::remove_dir_all(&temp_dir);
+        if !errors.is_empty() {
+            return Err(format!(
+                "Failed to move temp to working path, {} was kept: {}",
+                temp_dir.display(),
+                errors.join(", ")
+            ));
+        }
+
+        fs::remove_dir(temp_dir).map_err(|e| format!("Failed to delete folder: {}", e))
     }
 
     pub fn extract_id_and_filename(link: &str) -> Option<(String, String)> {
@@ -630,20 +1523,18 @@ impl Utils {
         }
     }
 
-    pub fn get_version_from_modinfo(modinfo_path: &PathBuf) -> Option<String> {
-        let file: fs::File = fs::File::open(&modinfo_path).ok()?;
+    pub fn get_version_from_modinfo(modinfo_path: &Path) -> Option<String> {
+        let file: fs::File = fs::File::open(modinfo_path).ok()?;
         let reader: BufReader<fs::File> = BufReader::new(file);
 
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                let line_trimmed = line.trim_start();
-                // Compara a versão em lowercase para case-insensitive
-                if line_trimmed.to_lowercase().starts_with("\"version\"") {
-                    let parts: Vec<&str> = line_trimmed.split(':').collect();
-                    if parts.len() >= 2 {
-                        let version_raw = parts[1].trim().trim_matches(',').trim_matches('"');
-                        return Some(version_raw.to_string());
-                    }
+        for line in reader.lines().map_while(Result::ok) {
+            let line_trimmed = line.trim_start();
+            // Compara a versão em lowercase para case-insensitive
+            if line_trimmed.to_lowercase().starts_with("\"version\"") {
+                let parts: Vec<&str> = line_trimmed.split(':').collect();
+                if parts.len() >= 2 {
+                    let version_raw = parts[1].trim().trim_matches(',').trim_matches('"');
+                    return Some(version_raw.to_string());
                 }
             }
         }
@@ -651,89 +1542,15 @@ impl Utils {
         None
     }
 
-    pub fn get_mod_id(mod_path: &PathBuf) -> Option<String> {
-        if mod_path.extension()? == "zip" {
-            LogsInstance::print(
-                "vs_updater does not support zip mods yet",
-                colored::Color::BrightYellow,
-            );
-            return None;
-        }
-
-        let mod_id_path: PathBuf = mod_path.join("modid.txt");
-
-        if !mod_id_path.exists() {
-            LogsInstance::print(
-                format!(
-                    "The mod {} does not have a modid.txt and cannot be updated",
-                    mod_path
-                        .file_name()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or("<unknown>")
-                )
-                .as_str(),
-                colored::Color::BrightRed,
-            );
-            return None;
-        }
-
-        let contents: String = fs::read_to_string(mod_id_path).ok()?;
-
-        // First line only
-        let first_non_empty_line = contents
-            .lines()
-            .map(|line| line.trim())
-            .find(|line| !line.is_empty())?;
-
-        Some(first_non_empty_line.to_string())
-    }
-
-    pub fn get_mod_fileid(mod_path: &PathBuf) -> Option<String> {
-        if mod_path.extension()? == "zip" {
-            LogsInstance::print(
-                "vs_updater does not support zip mods yet",
-                colored::Color::BrightYellow,
-            );
-            return None;
-        }
-
-        let mod_id_path: PathBuf = mod_path.join("modid.txt");
-
-        if !mod_id_path.exists() {
-            LogsInstance::print(
-                format!(
-                    "The mod {} does not have a modid.txt and cannot be updated",
-                    mod_path
-                        .file_name()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or("<unknown>")
-                )
-                .as_str(),
-                colored::Color::BrightRed,
-            );
-            return None;
-        }
-
-        let contents: String = fs::read_to_string(mod_id_path).ok()?;
-
-        // 2 Line only
-        let mut non_empty_lines = contents
-            .lines()
-            .map(|line| line.trim())
-            .filter(|line| !line.is_empty());
-
-        non_empty_lines.nth(1).map(|line| line.to_string())
-    }
-
     pub fn get_updated_path_from_version(
-        path: &PathBuf,
+        path: &Path,
         mod_name: &OsStr,
         new_version: &str,
     ) -> Option<PathBuf> {
         let filename: &str = match mod_name.to_str() {
             Some(name) => name,
             None => {
-                LogsInstance::print(
+                LogsInstance::error(
                     format!("Invalid file name (UTF-8): {:?}", mod_name).as_str(),
                     colored::Color::BrightRed,
                 );
@@ -745,7 +1562,7 @@ impl Utils {
             match filename.strip_suffix(".zip") {
                 Some(t) => t,
                 None => {
-                    LogsInstance::print(
+                    LogsInstance::error(
                         format!("Error while removing the extension .zip from {}", filename)
                             .as_str(),
                         colored::Color::BrightRed,
@@ -760,7 +1577,7 @@ impl Utils {
         let last_underscore = match trimmed.rfind('_') {
             Some(i) => i,
             None => {
-                LogsInstance::print(
+                LogsInstance::error(
                     format!("Mod does not contain '_' for versions: {}", trimmed).as_str(),
                     colored::Color::BrightRed,
                 );
@@ -779,7 +1596,8 @@ impl Utils {
     }
 
     pub fn get_latest_arm64_release() -> Option<String> {
-        let api_url = "https://api.github.com/repos/anegostudios/VintagestoryServerArm64/releases/latest";
+        let api_url =
+            "https://api.github.com/repos/anegostudios/VintagestoryServerArm64/releases/latest";
 
         let output = Command::new("wget")
             .arg("-q")
@@ -805,19 +1623,17 @@ impl Utils {
 
     pub fn get_mod_last_id_name_by_ping_url(
         ping_url: &String,
-        no_pre_mods: bool,
+        channel: Channel,
     ) -> (Option<i64>, Option<String>) {
         let mut biggest_id: Option<i64> = None;
         let mut biggest_filename: Option<String> = None;
 
-        if let Some(html) = Utils::url_result(&ping_url) {
+        if let Some(html) = Utils::url_result(ping_url) {
             let links: Vec<String> = Utils::extract_download_links(&html);
             for link in links {
                 if let Some((id_str, filename)) = Utils::extract_id_and_filename(&link) {
-                    if no_pre_mods {
-                        if filename.contains("-pre") || filename.contains("-rc") {
-                            continue;
-                        }
+                    if !channel.accepts_label(&filename) {
+                        continue;
                     }
                     match id_str.parse::<i64>() {
                         Ok(id) => {
@@ -828,21 +1644,21 @@ impl Utils {
                             });
                         }
                         Err(_) => {
-                            LogsInstance::print(
+                            LogsInstance::error(
                                 format!("Failed to parse id as integer: {}", id_str).as_str(),
                                 colored::Color::BrightRed,
                             );
                         }
                     }
                 } else {
-                    LogsInstance::print(
+                    LogsInstance::error(
                         format!("Invalid format link: {}", link).as_str(),
                         colored::Color::BrightRed,
                     );
                 }
             }
         } else {
-            LogsInstance::print(
+            LogsInstance::error(
                 format!("Failed to get mod html: {}", ping_url).as_str(),
                 colored::Color::BrightRed,
             );
@@ -852,157 +1668,59 @@ impl Utils {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct GameVersion {
-    pub major: u32,
-    pub minor: u32,
-    pub patch: u32,
-    pub pre_version: u32,
-    pub rc_version: u32,
-}
-
-impl GameVersion {
-    pub fn from_str(version: &str) -> Option<Self> {
-        let cleaned: &str = version.strip_suffix(".txt").unwrap_or(version);
-
-        let mut base_part: &str = cleaned;
-        let mut pre_version: u32 = 0;
-        let mut rc_version: u32 = 0;
-
-        if let Some((base, suffix)) = cleaned.split_once('-') {
-            base_part = base;
-            let parts: Vec<&str> = suffix.split('.').collect();
-            if parts.len() == 2 {
-                match parts[0] {
-                    "pre" => pre_version = parts[1].parse().ok()?,
-                    "rc" => rc_version = parts[1].parse().ok()?,
-                    _ => return None,
-                }
-            }
-        }
-
-        let parts: Vec<&str> = base_part.split('.').collect();
-        if parts.len() != 3 {
-            return None;
-        }
-
-        Some(GameVersion {
-            major: parts[0].parse().ok()?,
-            minor: parts[1].parse().ok()?,
-            patch: parts[2].parse().ok()?,
-            pre_version,
-            rc_version,
-        })
-    }
-
-    pub fn to_string(&self) -> String {
-        if self.pre_version > 0 {
-            return format!(
-                "{}.{}.{}-pre.{}",
-                self.major, self.minor, self.patch, self.pre_version
-            );
-        }
-
-        if self.rc_version > 0 {
-            return format!(
-                "{}.{}.{}-rc.{}",
-                self.major, self.minor, self.patch, self.rc_version
-            );
-        }
-
-        format!("{}.{}.{}", self.major, self.minor, self.patch)
-    }
-
-    pub fn increment_patch(&mut self) {
-        self.rc_version = 0;
-        self.pre_version = 0;
-        self.patch += 1;
-    }
-
-    pub fn increment_minor(&mut self) {
-        self.rc_version = 0;
-        self.pre_version = 0;
-        self.patch = 0;
-        self.minor += 1;
-    }
-
-    pub fn increment_major(&mut self) {
-        self.rc_version = 0;
-        self.pre_version = 0;
-        self.patch = 0;
-        self.minor = 0;
-        self.major += 1;
-    }
-
-    pub fn increment_pre(&mut self) {
-        self.rc_version = 0;
-        self.pre_version += 1;
-    }
-
-    pub fn increment_rc(&mut self) {
-        self.pre_version = 0;
-        self.rc_version += 1;
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
 
-    pub fn is_pre(&self) -> bool {
-        if self.pre_version > 0 {
-            return true;
-        }
-        false
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
     }
 
-    pub fn is_rc(&self) -> bool {
-        if self.rc_version > 0 {
-            return true;
-        }
-        false
-    }
-
-    pub fn remove_pre_and_rc(&mut self) {
-        self.pre_version = 0;
-        self.rc_version = 0;
+    #[test]
+    fn utc_datetime_known_dates() {
+        assert_eq!(Utils::utc_datetime(at(0)), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(Utils::utc_datetime(at(951_782_400)), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(
+            Utils::utc_datetime(at(1_709_251_199)),
+            (2024, 2, 29, 23, 59, 59)
+        );
+        assert_eq!(
+            Utils::format_datetime(at(1_738_367_999)),
+            "2025-01-31 23:59:59 UTC"
+        );
+        assert_eq!(Utils::file_timestamp(at(1_738_367_999)), "20250131-235959");
     }
 
-    pub fn equals(&self, game_version: GameVersion) -> bool {
-        if self.major == game_version.major
-            && self.minor == game_version.minor
-            && self.patch == game_version.patch
-            && self.pre_version == game_version.pre_version
-            && self.rc_version == game_version.rc_version
-        {
-            return true;
+    #[test]
+    fn parse_date_round_trip() {
+        for date in [
+            "1970-01-01",
+            "2000-02-29",
+            "2023-12-31",
+            "2024-02-29",
+            "2024-03-01",
+            "2100-02-28",
+            "2100-03-01",
+        ] {
+            let secs: u64 = Utils::parse_date(date).unwrap();
+            assert_eq!(secs % 86400, 0);
+
+            let (year, month, day, ..) = Utils::utc_datetime(at(secs));
+            assert_eq!(format!("{:04}-{:02}-{:02}", year, month, day), date);
         }
-        return false;
-    }
 
-    pub fn empty(&self) -> bool {
-        if self.major == 0 && self.minor == 0 && self.patch == 0 {
-            return true;
-        }
-        return false;
+        assert_eq!(Utils::parse_date(" 2024-03-01 "), Some(1_709_251_200));
     }
 
-    pub fn bigger_than(&self, version: GameVersion) -> bool {
-        if version.major > self.major {
-            print!("1");
-            return false;
-        } else if version.minor > self.minor {
-            print!("2");
-            return false;
-        } else if version.patch > self.patch {
-            print!("3");
-            return false;
-        }
-        // If provided version contains rc and pre version from this game version exists, automatically provided is bigger
-        else if version.rc_version > 0 && self.pre_version > 0 {
-            print!("4");
-            return false;
-        }
-        // If provided rc version is bigger than this rc version and this rc version exists
-        else if version.rc_version > self.rc_version && self.rc_version > 0 {
-            print!("5");
-            return false;
-        }
-        true
+    #[test]
+    fn parse_date_invalid() {
+        assert_eq!(Utils::parse_date("2024-13-01"), None);
+        assert_eq!(Utils::parse_date("2024-00-10"), None);
+        assert_eq!(Utils::parse_date("2024-01-32"), None);
+        assert_eq!(Utils::parse_date("2024/01/31"), None);
+        assert_eq!(Utils::parse_date("yesterday"), None);
+        // Before the unix epoch
+        assert_eq!(Utils::parse_date("1969-12-31"), None);
     }
 }
@@ -0,0 +1,638 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Game version like 1.20.0, 1.20.0-pre.1 or 1.20.0-rc.2, ordered pre < rc < release,
+/// built by parsing and the incrementers so a version is never both pre and rc
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct GameVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+    pre_version: u32,
+    rc_version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseVersionError {
+    Empty,
+    /// Not three numbers separated by dots
+    InvalidFormat(String),
+    InvalidNumber(String),
+    /// Suffix other than -pre.N or -rc.N
+    InvalidSuffix(String),
+}
+
+impl fmt::Display for ParseVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseVersionError::Empty => write!(f, "empty version"),
+            ParseVersionError::InvalidFormat(version) => {
+                write!(f, "invalid version {}, expected major.minor.patch", version)
+            }
+            ParseVersionError::InvalidNumber(number) => {
+                write!(f, "invalid version number: {}", number)
+            }
+            ParseVersionError::InvalidSuffix(suffix) => {
+                write!(
+                    f,
+                    "invalid version suffix {}, expected pre.N or rc.N",
+                    suffix
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseVersionError {}
+
+impl GameVersion {
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+
+    pub fn patch(&self) -> u32 {
+        self.patch
+    }
+
+    pub fn increment_patch(&mut self) {
+        self.rc_version = 0;
+        self.pre_version = 0;
+        self.patch += 1;
+    }
+
+    pub fn increment_minor(&mut self) {
+        self.rc_version = 0;
+        self.pre_version = 0;
+        self.patch = 0;
+        self.minor += 1;
+    }
+
+    pub fn increment_major(&mut self) {
+        self.rc_version = 0;
+        self.pre_version = 0;
+        self.patch = 0;
+        self.minor = 0;
+        self.major += 1;
+    }
+
+    pub fn increment_pre(&mut self) {
+        self.rc_version = 0;
+        self.pre_version += 1;
+    }
+
+    pub fn increment_rc(&mut self) {
+        self.pre_version = 0;
+        self.rc_version += 1;
+    }
+
+    pub fn is_pre(&self) -> bool {
+        self.pre_version > 0
+    }
+
+    pub fn is_rc(&self) -> bool {
+        self.rc_version > 0
+    }
+
+    pub fn remove_pre_and_rc(&mut self) {
+        self.pre_version = 0;
+        self.rc_version = 0;
+    }
+
+    /// 0.0.0, used when no version was found
+    pub fn empty(&self) -> bool {
+        self.major == 0 && self.minor == 0 && self.patch == 0
+    }
+
+    /// Position of the stage inside the same major.minor.patch: pre, rc, then release
+    fn stage(&self) -> (u32, u32) {
+        if self.is_pre() {
+            (0, self.pre_version)
+        } else if self.is_rc() {
+            (1, self.rc_version)
+        } else {
+            (2, 0)
+        }
+    }
+
+    /// Key of the comparisons, equality and hash agree with the order
+    fn key(&self) -> (u32, u32, u32, (u32, u32)) {
+        (self.major, self.minor, self.patch, self.stage())
+    }
+
+    fn parse_number(number: &str) -> Result<u32, ParseVersionError> {
+        if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ParseVersionError::InvalidNumber(number.to_string()));
+        }
+        number
+            .parse()
+            .map_err(|_| ParseVersionError::InvalidNumber(number.to_string()))
+    }
+}
+
+impl PartialEq for GameVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for GameVersion {}
+
+impl Hash for GameVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl Ord for GameVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for GameVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for GameVersion {
+    type Err = ParseVersionError;
+
+    /// Accepts an optional v prefix, like the tags of the mod database
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let version: &str = version.trim();
+        let cleaned: &str = version.strip_prefix('v').unwrap_or(version);
+
+        if cleaned.is_empty() {
+            return Err(ParseVersionError::Empty);
+        }
+
+        let (base, suffix): (&str, Option<&str>) = match cleaned.split_once('-') {
+            Some((base, suffix)) => (base, Some(suffix)),
+            None => (cleaned, None),
+        };
+
+        let parts: Vec<&str> = base.split('.').collect();
+        if parts.len() != 3 {
+            return Err(ParseVersionError::InvalidFormat(version.to_string()));
+        }
+
+        let mut parsed: GameVersion = GameVersion {
+            major: GameVersion::parse_number(parts[0])?,
+            minor: GameVersion::parse_number(parts[1])?,
+            patch: GameVersion::parse_number(parts[2])?,
+            pre_version: 0,
+            rc_version: 0,
+        };
+
+        if let Some(suffix) = suffix {
+            let (stage, number): (&str, &str) = suffix
+                .split_once('.')
+                .ok_or_else(|| ParseVersionError::InvalidSuffix(suffix.to_string()))?;
+
+            let number: u32 = GameVersion::parse_number(number)
+                .map_err(|_| ParseVersionError::InvalidSuffix(suffix.to_string()))?;
+
+            // pre.0 and rc.0 would print as the release
+            if number == 0 {
+                return Err(ParseVersionError::InvalidSuffix(suffix.to_string()));
+            }
+
+            match stage {
+                "pre" => parsed.pre_version = number,
+                "rc" => parsed.rc_version = number,
+                _ => return Err(ParseVersionError::InvalidSuffix(suffix.to_string())),
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl TryFrom<String> for GameVersion {
+    type Error = ParseVersionError;
+
+    fn try_from(version: String) -> Result<Self, Self::Error> {
+        version.parse()
+    }
+}
+
+impl From<GameVersion> for String {
+    fn from(version: GameVersion) -> Self {
+        version.to_string()
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pre_version > 0 {
+            return write!(
+                f,
+                "{}.{}.{}-pre.{}",
+                self.major, self.minor, self.patch, self.pre_version
+            );
+        }
+
+        if self.rc_version > 0 {
+            return write!(
+                f,
+                "{}.{}.{}-rc.{}",
+                self.major, self.minor, self.patch, self.rc_version
+            );
+        }
+
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Version pattern like 1.21.x, 1.x or 1.21.3, missing parts are wildcards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionConstraint {
+    pub major: u32,
+    pub minor: Option<u32>,
+    pub patch: Option<u32>,
+}
+
+impl VersionConstraint {
+    /// True when the version is inside the pattern, any stage of the version is accepted
+    pub fn matches(&self, version: &GameVersion) -> bool {
+        version.major == self.major
+            && self.minor.is_none_or(|minor| version.minor == minor)
+            && self.patch.is_none_or(|patch| version.patch == patch)
+    }
+
+    /// True when the version is not newer than the pattern, used as upper bound
+    pub fn allows(&self, version: &GameVersion) -> bool {
+        if version.major != self.major {
+            return version.major < self.major;
+        }
+
+        let minor: u32 = match self.minor {
+            Some(minor) => minor,
+            None => return true,
+        };
+        if version.minor != minor {
+            return version.minor < minor;
+        }
+
+        self.patch.is_none_or(|patch| version.patch <= patch)
+    }
+}
+
+impl FromStr for VersionConstraint {
+    type Err = ParseVersionError;
+
+    fn from_str(constraint: &str) -> Result<Self, Self::Err> {
+        let constraint: &str = constraint.trim();
+        let cleaned: &str = constraint.strip_prefix('v').unwrap_or(constraint);
+
+        if cleaned.is_empty() {
+            return Err(ParseVersionError::Empty);
+        }
+
+        let parts: Vec<&str> = cleaned.split('.').collect();
+        if parts.len() > 3 {
+            return Err(ParseVersionError::InvalidFormat(constraint.to_string()));
+        }
+
+        let mut numbers: Vec<Option<u32>> = Vec::new();
+        for part in &parts {
+            if *part == "x" || *part == "*" {
+                numbers.push(None);
+            } else if numbers.last().is_some_and(|last| last.is_none()) {
+                // 1.x.3 has no meaning
+                return Err(ParseVersionError::InvalidFormat(constraint.to_string()));
+            } else {
+                numbers.push(Some(GameVersion::parse_number(part)?));
+            }
+        }
+
+        Ok(VersionConstraint {
+            major: numbers[0]
+                .ok_or_else(|| ParseVersionError::InvalidFormat(constraint.to_string()))?,
+            minor: numbers.get(1).copied().flatten(),
+            patch: numbers.get(2).copied().flatten(),
+        })
+    }
+}
+
+impl fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => write!(f, "{}.{}.{}", self.major, minor, patch),
+            (Some(minor), None) => write!(f, "{}.{}.x", self.major, minor),
+            _ => write!(f, "{}.x", self.major),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn version(text: &str) -> GameVersion {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_release() {
+        assert_eq!(
+            version("1.20.3"),
+            GameVersion {
+                major: 1,
+                minor: 20,
+                patch: 3,
+                pre_version: 0,
+                rc_version: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn parses_pre_and_rc() {
+        let pre: GameVersion = version("1.21.0-pre.2");
+        assert_eq!((pre.pre_version, pre.rc_version), (2, 0));
+        assert!(pre.is_pre() && !pre.is_rc());
+
+        let rc: GameVersion = version("1.21.0-rc.4");
+        assert_eq!((rc.pre_version, rc.rc_version), (0, 4));
+        assert!(rc.is_rc() && !rc.is_pre());
+    }
+
+    #[test]
+    fn parses_v_prefix_and_whitespace() {
+        assert_eq!(version("v1.20.0"), version("1.20.0"));
+        assert_eq!(version(" 1.20.0\n"), version("1.20.0"));
+    }
+
+    #[test]
+    fn rejects_invalid_versions() {
+        assert_eq!("".parse::<GameVersion>(), Err(ParseVersionError::Empty));
+        assert_eq!("v".parse::<GameVersion>(), Err(ParseVersionError::Empty));
+
+        for invalid in ["1.20", "1.20.0.1", "1..0", "a.b.c"] {
+            assert!(invalid.parse::<GameVersion>().is_err(), "{}", invalid);
+        }
+
+        assert_eq!(
+            "1.20".parse::<GameVersion>(),
+            Err(ParseVersionError::InvalidFormat(String::from("1.20")))
+        );
+        assert_eq!(
+            "1.x.0".parse::<GameVersion>(),
+            Err(ParseVersionError::InvalidNumber(String::from("x")))
+        );
+        assert_eq!(
+            "1.-1.0".parse::<GameVersion>(),
+            Err(ParseVersionError::InvalidFormat(String::from("1.-1.0")))
+        );
+        assert_eq!(
+            "1.+1.0".parse::<GameVersion>(),
+            Err(ParseVersionError::InvalidNumber(String::from("+1")))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_suffixes() {
+        for invalid in [
+            "1.20.0-beta.1",
+            "1.20.0-pre",
+            "1.20.0-pre.",
+            "1.20.0-pre.x",
+            "1.20.0-pre.0",
+            "1.20.0-rc.0",
+            "1.20.0-",
+        ] {
+            assert!(
+                matches!(
+                    invalid.parse::<GameVersion>(),
+                    Err(ParseVersionError::InvalidSuffix(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in ["0.0.0", "1.20.0", "1.21.0-pre.1", "1.21.0-rc.12", "10.0.99"] {
+            assert_eq!(version(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn orders_by_numbers() {
+        assert!(version("1.20.0") > version("1.19.9"));
+        assert!(version("1.19.10") > version("1.19.9"));
+        assert!(version("2.0.0") > version("1.99.99"));
+        assert!(version("1.20.1") > version("1.20.0"));
+        assert!(version("1.9.0") < version("1.10.0"));
+    }
+
+    #[test]
+    fn orders_pre_before_rc_before_release() {
+        assert!(version("1.21.0-pre.1") < version("1.21.0-pre.2"));
+        assert!(version("1.21.0-pre.9") < version("1.21.0-rc.1"));
+        assert!(version("1.21.0-rc.1") < version("1.21.0-rc.2"));
+        assert!(version("1.21.0-rc.9") < version("1.21.0"));
+        assert!(version("1.21.0-pre.1") < version("1.21.0"));
+    }
+
+    #[test]
+    fn unstable_is_newer_than_previous_release() {
+        assert!(version("1.21.0-pre.1") > version("1.20.12"));
+        assert!(version("1.21.0-rc.1") > version("1.20.99"));
+        assert!(version("1.20.1-rc.1") > version("1.20.0"));
+    }
+
+    #[test]
+    fn sorts_a_mixed_list() {
+        let mut versions: Vec<GameVersion> = [
+            "1.21.0",
+            "1.20.0",
+            "1.21.0-rc.1",
+            "1.19.8",
+            "1.21.0-pre.3",
+            "1.21.0-pre.1",
+            "1.20.10",
+            "1.20.9",
+        ]
+        .iter()
+        .map(|text| version(text))
+        .collect();
+        versions.sort();
+
+        let sorted: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "1.19.8",
+                "1.20.0",
+                "1.20.9",
+                "1.20.10",
+                "1.21.0-pre.1",
+                "1.21.0-pre.3",
+                "1.21.0-rc.1",
+                "1.21.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn equality_and_hash_agree() {
+        assert_eq!(version("1.20.0"), version("v1.20.0"));
+        assert_ne!(version("1.20.0"), version("1.20.0-rc.1"));
+        assert_eq!(
+            version("1.20.0").cmp(&version("1.20.0")),
+            std::cmp::Ordering::Equal
+        );
+
+        let set: HashSet<GameVersion> = ["1.20.0", "v1.20.0", "1.20.0-rc.1"]
+            .iter()
+            .map(|text| version(text))
+            .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn equality_follows_the_order() {
+        // Not reachable through parsing, the pre stage wins like in the order
+        let both: GameVersion = GameVersion {
+            major: 1,
+            minor: 20,
+            patch: 0,
+            pre_version: 1,
+            rc_version: 3,
+        };
+        assert_eq!(
+            both.cmp(&version("1.20.0-pre.1")),
+            std::cmp::Ordering::Equal
+        );
+        assert_eq!(both, version("1.20.0-pre.1"));
+
+        let set: HashSet<GameVersion> = [both, version("1.20.0-pre.1")].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn increments() {
+        let mut v: GameVersion = version("1.20.3-rc.2");
+        v.increment_patch();
+        assert_eq!(v, version("1.20.4"));
+
+        v.increment_pre();
+        assert_eq!(v, version("1.20.4-pre.1"));
+        v.increment_rc();
+        assert_eq!(v, version("1.20.4-rc.1"));
+        v.increment_rc();
+        assert_eq!(v, version("1.20.4-rc.2"));
+
+        v.increment_minor();
+        assert_eq!(v, version("1.21.0"));
+        v.increment_major();
+        assert_eq!(v, version("2.0.0"));
+
+        let mut unstable: GameVersion = version("1.20.0-pre.3");
+        unstable.remove_pre_and_rc();
+        assert_eq!(unstable, version("1.20.0"));
+    }
+
+    #[test]
+    fn empty_version() {
+        assert!(GameVersion::default().empty());
+        assert!(version("0.0.0").empty());
+        assert!(!version("0.0.1").empty());
+        assert!(!version("1.0.0").empty());
+    }
+
+    #[test]
+    fn serde_uses_the_string_form() {
+        let json: String = serde_json::to_string(&version("1.21.0-rc.3")).unwrap();
+        assert_eq!(json, "\"1.21.0-rc.3\"");
+
+        let parsed: GameVersion = serde_json::from_str("\"1.20.12\"").unwrap();
+        assert_eq!(parsed, version("1.20.12"));
+
+        assert!(serde_json::from_str::<GameVersion>("\"1.20\"").is_err());
+        assert!(serde_json::from_str::<GameVersion>("12").is_err());
+    }
+
+    fn constraint(text: &str) -> VersionConstraint {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_constraints() {
+        assert_eq!(
+            constraint("1.21.x"),
+            VersionConstraint {
+                major: 1,
+                minor: Some(21),
+                patch: None,
+            }
+        );
+        assert_eq!(constraint("1.21"), constraint("1.21.x"));
+        assert_eq!(constraint("1.21.*"), constraint("1.21.x"));
+        assert_eq!(constraint("1.x"), constraint("1"));
+        assert_eq!(constraint("1.x.x"), constraint("1.x"));
+        assert_eq!(constraint("v1.21.3").patch, Some(3));
+
+        for invalid in ["", "x", "x.21", "1.x.3", "1.21.3.1", "1.a"] {
+            assert!(invalid.parse::<VersionConstraint>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn constraint_display() {
+        for text in ["1.21.x", "1.x", "1.21.3"] {
+            assert_eq!(constraint(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn constraint_matches_series() {
+        let series: VersionConstraint = constraint("1.21.x");
+        assert!(series.matches(&version("1.21.0")));
+        assert!(series.matches(&version("1.21.14")));
+        assert!(series.matches(&version("1.21.0-rc.1")));
+        assert!(!series.matches(&version("1.22.0")));
+        assert!(!series.matches(&version("1.20.9")));
+        assert!(!series.matches(&version("2.21.0")));
+
+        assert!(constraint("1.x").matches(&version("1.99.0")));
+        assert!(!constraint("1.x").matches(&version("2.0.0")));
+        assert!(constraint("1.21.3").matches(&version("1.21.3-pre.1")));
+        assert!(!constraint("1.21.3").matches(&version("1.21.4")));
+    }
+
+    #[test]
+    fn constraint_as_upper_bound() {
+        let max: VersionConstraint = constraint("1.21.x");
+        assert!(max.allows(&version("1.20.9")));
+        assert!(max.allows(&version("1.21.99")));
+        assert!(max.allows(&version("0.99.0")));
+        assert!(!max.allows(&version("1.22.0-pre.1")));
+        assert!(!max.allows(&version("2.0.0")));
+
+        let exact: VersionConstraint = constraint("1.21.3");
+        assert!(exact.allows(&version("1.21.3")));
+        assert!(exact.allows(&version("1.21.3-rc.2")));
+        assert!(!exact.allows(&version("1.21.4")));
+
+        assert!(constraint("1.x").allows(&version("1.99.99")));
+        assert!(!constraint("1.x").allows(&version("2.0.0")));
+    }
+}